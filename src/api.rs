@@ -1,22 +1,67 @@
+use std::{fs, io, path::Path};
+
 use axum::{middleware, Router};
 
-use crate::{persistence::PersistenceManager, state::screensaver_manager::ScreensaverManager};
+use crate::{
+    api::{canon::CanonMode, ingest_limits::IngestLimits},
+    domain::actions::{
+        image::{FetchCanon, FetchScreensaverExclusions},
+        screensaver::FetchScreensaverRotations,
+    },
+    persistence::PersistenceManager,
+    state::{image_cache::ImageCache, screensaver_manager::ScreensaverManager},
+};
 
 mod canon;
+mod content_hash;
+mod iiif;
+mod image_derivatives;
 mod image_dimensions;
+mod image_formats;
+mod image_metadata;
+mod image_orientation;
 mod image_server;
+mod ingest_limits;
+mod perceptual_hash;
 mod request_tracing;
 mod routing;
 
 const IMAGES_DIR: &str = "/var/lib/photo_manager_server/images";
+/// Holds uploads until they're validated and saved. It's on the same filesystem as [IMAGES_DIR],
+/// so they can be moved into it atomically.
+const UPLOAD_STAGING_DIR: &str = "/var/lib/photo_manager_server/staging";
+/// Holds resumable uploads until they're finished. Like [UPLOAD_STAGING_DIR], it's on the same
+/// filesystem as [IMAGES_DIR].
+const RESUMABLE_UPLOADS_DIR: &str = "/var/lib/photo_manager_server/resumable";
+/// Holds the entries of [IMAGES_DIR] that a canon update moved aside since they aren't readable
+/// images. Like [UPLOAD_STAGING_DIR], it's on the same filesystem as [IMAGES_DIR].
+const QUARANTINE_DIR: &str = "/var/lib/photo_manager_server/quarantine";
+/// Holds the images uploaded by guests until they're approved, so they aren't served before.
+/// Like [UPLOAD_STAGING_DIR], it's on the same filesystem as [IMAGES_DIR].
+const PENDING_DIR: &str = "/var/lib/photo_manager_server/pending";
+/// Holds the derivatives generated from the images in [IMAGES_DIR].
+const DERIVATIVES_DIR: &str = "/var/lib/photo_manager_server/derivatives";
+/// Holds the images generated on demand, like IIIF outputs.
+const IMAGE_CACHE_DIR: &str = "/var/lib/photo_manager_server/cache";
+const IMAGE_CACHE_BUDGET: u64 = 1024 * 1024 * 1024; /* 1gb */
 
 pub async fn make_api_router(persistence_mngr: &PersistenceManager) -> Router {
-    let mut screensaver_mngr = ScreensaverManager::new();
-    canon::update_canon(&persistence_mngr, &mut screensaver_mngr)
-        .await
-        .expect("Canon should be updatable from startup");
+    let mut screensaver_mngr = restore_screensaver(persistence_mngr).await;
+    let limits = IngestLimits::from_env();
+    // Unreadable entries are skipped, so they don't keep the server from starting.
+    canon::update_canon(
+        &persistence_mngr,
+        &mut screensaver_mngr,
+        CanonMode::Skip,
+        &limits,
+    )
+    .await
+    .expect("Canon should be updatable from startup");
+    screensaver_mngr.persist_with(persistence_mngr.clone());
 
-    let image_server_router = image_server::create_image_server_router();
+    let image_cache = ImageCache::open(IMAGE_CACHE_DIR, IMAGE_CACHE_BUDGET)
+        .expect("Image cache should be openable from startup");
+    let image_server_router = image_server::create_image_server_router(image_cache, limits);
 
     let demo_router = routing::make_api_router(persistence_mngr, &screensaver_mngr);
 
@@ -25,3 +70,79 @@ pub async fn make_api_router(persistence_mngr: &PersistenceManager) -> Router {
         .merge(demo_router)
         .layer(middleware::from_fn(request_tracing::print_request_response))
 }
+
+/// Restores the screensaver from the saved rotations and the images already in the database.
+/// Changes on disk since then are merged in by updating the canon afterwards.
+async fn restore_screensaver(persistence_mngr: &PersistenceManager) -> ScreensaverManager {
+    let images = persistence_mngr
+        .fetch_canon()
+        .await
+        .expect("Images should be fetchable from startup");
+    let rotations = persistence_mngr
+        .fetch_screensaver_rotations()
+        .await
+        .expect("Screensaver rotations should be fetchable from startup");
+    let exclusions = persistence_mngr
+        .fetch_screensaver_exclusions()
+        .await
+        .expect("Screensaver exclusions should be fetchable from startup");
+
+    ScreensaverManager::restore(
+        rotations,
+        images
+            .into_iter()
+            .filter(|i| !exclusions.contains(&i.file_name))
+            .map(|i| (i.file_name.clone(), i))
+            .collect(),
+    )
+}
+
+// to prevent directory traversal attacks we ensure the path consists of exactly one normal
+// component
+fn path_is_valid(path: &str) -> bool {
+    let path = std::path::Path::new(path);
+    let mut components = path.components().peekable();
+
+    if let Some(first) = components.peek() {
+        if !matches!(first, std::path::Component::Normal(_)) {
+            return false;
+        }
+    }
+
+    components.count() == 1
+}
+
+/// Returns the directory holding the image, [PENDING_DIR] if it's pending approval.
+fn image_dir(file_name: &str) -> &'static str {
+    if Path::new(PENDING_DIR).join(file_name).exists() {
+        PENDING_DIR
+    } else {
+        IMAGES_DIR
+    }
+}
+
+/// Removes the images from [IMAGES_DIR] or [PENDING_DIR] along with their derivatives. Returns
+/// the file names of the removed images and of those that couldn't be removed. Images that are
+/// already gone count as removed.
+fn delete_image_files(file_names: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let mut deleted = Vec::new();
+    let mut failed = Vec::new();
+    for file_name in file_names {
+        match fs::remove_file(Path::new(image_dir(&file_name)).join(&file_name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                tracing::warn!("failed to delete {}: {}", file_name, e);
+                failed.push(file_name);
+                continue;
+            }
+            _ => {}
+        }
+
+        // Leftover derivatives are removed by the next canon update.
+        if let Err(e) = image_derivatives::delete_derivatives(&file_name) {
+            tracing::warn!("failed to delete derivatives of {}: {}", file_name, e);
+        }
+        deleted.push(file_name);
+    }
+
+    (deleted, failed)
+}
@@ -1,19 +1,43 @@
-use std::{fs, io};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fs::{self, DirEntry},
+    io,
+    num::NonZeroUsize,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
 
-use serde::Serialize;
+use chrono::{DateTime, NaiveDateTime, SubsecRound, Utc};
+use futures::{
+    future::{BoxFuture, Shared},
+    stream, FutureExt, StreamExt,
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     api::{
+        content_hash, image_derivatives,
         image_dimensions::{self, FetchImageDimensionsError},
-        IMAGES_DIR,
+        image_formats, image_metadata, image_orientation,
+        ingest_limits::IngestLimits,
+        perceptual_hash, IMAGES_DIR, QUARANTINE_DIR,
+    },
+    domain::{
+        actions::image::{
+            DiffCanon, FetchImageFingerprints, FetchScreensaverExclusions, UpdateCanon,
+        },
+        models::{CanonDiff, Image, ImageFile, ImageFingerprint},
+        screensaver::Screensaver,
     },
-    domain::{actions::image::UpdateCanon, models::Image, screensaver::Screensaver},
 };
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum FetchCanonError {
     IO(String),
+    Blocking(String),
     MultiIO(Vec<String>),
     FileNameConversionError,
     FetchDimensionsErrors(Vec<FetchDimensionsError>),
@@ -32,61 +56,354 @@ impl From<io::Error> for FetchCanonError {
     }
 }
 
-impl From<(String, FetchImageDimensionsError)> for FetchDimensionsError {
-    fn from((file_name, err): (String, FetchImageDimensionsError)) -> Self {
-        Self { file_name, err }
-    }
+/// How [update_canon] handles the entries of [IMAGES_DIR] that aren't readable images, like
+/// subdirectories, stray system files or partially copied images.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CanonMode {
+    /// Fails without updating anything.
+    #[default]
+    Strict,
+    /// Leaves them where they are. Images already saved under their names are kept as they are.
+    Skip,
+    /// Moves them into [QUARANTINE_DIR].
+    Quarantine,
+}
+
+/// An entry of [IMAGES_DIR] left out of the canon.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedEntry {
+    /// Converted lossily when it isn't UTF-8. `None` when the entry itself couldn't be read.
+    file_name: Option<String>,
+    reason: SkipReason,
+    /// The name it was moved to in [QUARANTINE_DIR].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quarantined_as: Option<String>,
+    /// Why it couldn't be moved to [QUARANTINE_DIR], in which case it was left in place.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quarantine_error: Option<String>,
 }
 
-impl From<(FetchImageDimensionsError, String)> for FetchDimensionsError {
-    fn from((err, file_name): (FetchImageDimensionsError, String)) -> Self {
-        Self { file_name, err }
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    UnreadableEntry(String),
+    FileNameNotUtf8,
+    NotAFile,
+    UnreadableImage(FetchImageDimensionsError),
+    /// Images this large aren't decoded, like they aren't uploaded.
+    #[serde(rename_all = "camelCase")]
+    TooManyPixels {
+        width: u32,
+        height: u32,
+        max_megapixels: f64,
+    },
+}
+
+struct FetchedCanon {
+    /// The images whose files were read.
+    images: Vec<ImageFile>,
+    /// The saved images whose files still match their fingerprints, so they weren't read.
+    unchanged: Vec<Image>,
+    skipped: Vec<SkippedEntry>,
+}
+
+/// Reads the files of [IMAGES_DIR], other than those matching the `fingerprints` of their saved
+/// images. The directory is listed on the blocking pool, then the files are read there a few at
+/// a time, so a scan doesn't hold up the other requests.
+async fn fetch_canon(
+    mode: CanonMode,
+    fingerprints: HashMap<String, ImageFingerprint>,
+    limits: &IngestLimits,
+) -> Result<FetchedCanon, FetchCanonError> {
+    let CanonListing {
+        to_read,
+        unchanged,
+        mut skipped,
+    } = run_blocking(move || list_canon(fingerprints)).await??;
+
+    let mut images = Vec::new();
+    let mut reads = stream::iter(to_read)
+        .map(|(entry, file_name, file_metadata)| {
+            let limits = limits.clone();
+            async move {
+                let read = tokio::task::spawn_blocking(move || {
+                    fetch_image_file(&file_name, &file_metadata, &limits)
+                })
+                .await;
+                (entry, read)
+            }
+        })
+        .buffer_unordered(scan_parallelism());
+    while let Some((entry, read)) = reads.next().await {
+        match read {
+            Ok(Ok(v)) => images.push(v),
+            Ok(Err(reason)) => skipped.push((Some(entry), reason)),
+            // Decoding panicked on the file.
+            Err(e) => skipped.push((
+                Some(entry),
+                SkipReason::UnreadableImage(FetchImageDimensionsError::ErrorOpeningImage(
+                    e.to_string(),
+                )),
+            )),
+        }
     }
+
+    let skipped = run_blocking(move || settle_skipped(mode, skipped)).await??;
+
+    Ok(FetchedCanon {
+        images,
+        unchanged,
+        skipped,
+    })
 }
 
-fn fetch_canon() -> Result<Vec<Image>, FetchCanonError> {
+/// The entries of [IMAGES_DIR], sorted by what a scan does with them.
+struct CanonListing {
+    /// The files to read, with their names and metadata.
+    to_read: Vec<(DirEntry, String, fs::Metadata)>,
+    unchanged: Vec<Image>,
+    skipped: Vec<(Option<DirEntry>, SkipReason)>,
+}
+
+fn list_canon(
+    mut fingerprints: HashMap<String, ImageFingerprint>,
+) -> Result<CanonListing, FetchCanonError> {
     fs::create_dir_all(IMAGES_DIR)?;
 
-    let images_dir: Vec<_> = fs::read_dir(IMAGES_DIR)?.collect();
-    let (oks, errs): (Vec<_>, Vec<_>) = images_dir.into_iter().partition(Result::is_ok);
-    if !errs.is_empty() {
-        let errs: Vec<_> = errs
+    let mut to_read = Vec::new();
+    let mut unchanged = Vec::new();
+    let mut skipped = Vec::new();
+    for entry in fs::read_dir(IMAGES_DIR)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                skipped.push((None, SkipReason::UnreadableEntry(e.to_string())));
+                continue;
+            }
+        };
+        let Some(file_name) = entry.file_name().to_str().map(str::to_string) else {
+            skipped.push((Some(entry), SkipReason::FileNameNotUtf8));
+            continue;
+        };
+        let file_metadata = match entry.metadata() {
+            Ok(m) if m.is_file() => m,
+            Ok(_) => {
+                skipped.push((Some(entry), SkipReason::NotAFile));
+                continue;
+            }
+            Err(e) => {
+                skipped.push((Some(entry), SkipReason::UnreadableEntry(e.to_string())));
+                continue;
+            }
+        };
+        if let Some(fingerprint) = fingerprints.remove(&file_name) {
+            if fingerprint.byte_size == file_metadata.len()
+                && Some(fingerprint.modified_at) == modified_at(&file_metadata)
+            {
+                unchanged.push(fingerprint.image);
+                continue;
+            }
+        }
+        to_read.push((entry, file_name, file_metadata));
+    }
+
+    Ok(CanonListing {
+        to_read,
+        unchanged,
+        skipped,
+    })
+}
+
+/// Fails on the skipped entries in [CanonMode::Strict], and moves them into [QUARANTINE_DIR] in
+/// [CanonMode::Quarantine].
+fn settle_skipped(
+    mode: CanonMode,
+    skipped: Vec<(Option<DirEntry>, SkipReason)>,
+) -> Result<Vec<SkippedEntry>, FetchCanonError> {
+    let skipped = match mode {
+        CanonMode::Strict if !skipped.is_empty() => return Err(strict_error(skipped)),
+        CanonMode::Strict | CanonMode::Skip => skipped
+            .into_iter()
+            .map(|(entry, reason)| SkippedEntry {
+                file_name: entry.map(|e| e.file_name().to_string_lossy().into_owned()),
+                reason,
+                quarantined_as: None,
+                quarantine_error: None,
+            })
+            .collect(),
+        CanonMode::Quarantine => skipped
             .into_iter()
-            .map(Result::unwrap_err)
-            .map(|e| e.to_string())
-            .collect();
-        return Err(FetchCanonError::MultiIO(errs));
-    }
-
-    let images_dir: Vec<_> = oks.into_iter().map(Result::unwrap).collect();
-
-    let mut oks = Vec::new();
-    let mut errs = Vec::new();
-    for entry in images_dir {
-        let file_name = entry
-            .file_name()
-            .to_str()
-            .ok_or(FetchCanonError::FileNameConversionError)?
-            .to_string();
-        match fetch_dimensions(&file_name) {
-            Ok(v) => oks.push(v),
-            Err(e) => errs.push(e),
+            .map(|(entry, reason)| {
+                let Some(entry) = entry else {
+                    return SkippedEntry {
+                        file_name: None,
+                        reason,
+                        quarantined_as: None,
+                        quarantine_error: None,
+                    };
+                };
+                let (quarantined_as, quarantine_error) = match quarantine(&entry) {
+                    Ok(name) => (Some(name), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                SkippedEntry {
+                    file_name: Some(entry.file_name().to_string_lossy().into_owned()),
+                    reason,
+                    quarantined_as,
+                    quarantine_error,
+                }
+            })
+            .collect(),
+    };
+
+    Ok(skipped)
+}
+
+async fn run_blocking<T: 'static + Send>(
+    f: impl 'static + Send + FnOnce() -> T,
+) -> Result<T, FetchCanonError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| FetchCanonError::Blocking(e.to_string()))
+}
+
+/// How many files a scan reads at once, from `CANON_SCAN_PARALLELISM`. The number of CPUs when
+/// it's unset.
+fn scan_parallelism() -> usize {
+    let default = || thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    match env::var("CANON_SCAN_PARALLELISM") {
+        Ok(v) => match v.parse() {
+            Ok(v) if v > 0 => v,
+            _ => {
+                tracing::warn!(
+                    "CANON_SCAN_PARALLELISM must be a positive number, not {}",
+                    v
+                );
+                default()
+            }
+        },
+        Err(_) => default(),
+    }
+}
+
+/// Reports the skipped entries the way the canon has always failed on them: unreadable entries
+/// first, then file names that aren't UTF-8, then files that aren't readable images.
+fn strict_error(skipped: Vec<(Option<DirEntry>, SkipReason)>) -> FetchCanonError {
+    let mut io_errs = Vec::new();
+    let mut dimensions_errs = Vec::new();
+    let mut non_utf8_name = false;
+    for (entry, reason) in skipped {
+        let file_name = || {
+            entry
+                .as_ref()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        match reason {
+            SkipReason::UnreadableEntry(e) => io_errs.push(e),
+            SkipReason::FileNameNotUtf8 => non_utf8_name = true,
+            SkipReason::NotAFile => dimensions_errs.push(FetchDimensionsError {
+                file_name: file_name(),
+                err: FetchImageDimensionsError::ErrorOpeningImage("not a file".to_string()),
+            }),
+            SkipReason::UnreadableImage(err) => dimensions_errs.push(FetchDimensionsError {
+                file_name: file_name(),
+                err,
+            }),
+            SkipReason::TooManyPixels {
+                width,
+                height,
+                max_megapixels,
+            } => dimensions_errs.push(FetchDimensionsError {
+                file_name: file_name(),
+                err: FetchImageDimensionsError::ErrorOpeningImage(format!(
+                    "{}x{} is over {} megapixels",
+                    width, height, max_megapixels
+                )),
+            }),
         }
     }
-    if errs.is_empty() {
-        Ok(oks)
+
+    if !io_errs.is_empty() {
+        FetchCanonError::MultiIO(io_errs)
+    } else if non_utf8_name {
+        FetchCanonError::FileNameConversionError
     } else {
-        Err(FetchCanonError::FetchDimensionsErrors(errs))
+        FetchCanonError::FetchDimensionsErrors(dimensions_errs)
+    }
+}
+
+/// Moves the entry into [QUARANTINE_DIR], numbering its name if it's taken there.
+/// Returns the name it was moved to.
+fn quarantine(entry: &DirEntry) -> io::Result<String> {
+    fs::create_dir_all(QUARANTINE_DIR)?;
+
+    let file_name = entry.file_name();
+    let mut target = Path::new(QUARANTINE_DIR).join(&file_name);
+    let mut n = 1;
+    while target.symlink_metadata().is_ok() {
+        let mut numbered = file_name.clone();
+        numbered.push(format!(".{}", n));
+        target = Path::new(QUARANTINE_DIR).join(numbered);
+        n += 1;
     }
+    fs::rename(entry.path(), &target)?;
+
+    Ok(target
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// When the file was last modified, truncated to the microsecond like it's saved.
+pub fn modified_at(file_metadata: &fs::Metadata) -> Option<NaiveDateTime> {
+    let modified = file_metadata.modified().ok()?;
+    Some(DateTime::<Utc>::from(modified).naive_utc().trunc_subsecs(6))
 }
 
-fn fetch_dimensions(file_name: &str) -> Result<Image, FetchDimensionsError> {
-    let (width, height) = image_dimensions::fetch_image_dimensions(file_name)
-        .map_err(|e| (file_name.to_string(), e))?;
-    Ok(Image {
-        file_name: file_name.to_string(),
-        width,
-        height,
+/// Reads the image, unless its dimensions are over the `limits`. The `file_metadata` is read
+/// beforehand, so a file modified while it's read doesn't match its fingerprint on the next canon
+/// update.
+fn fetch_image_file(
+    file_name: &str,
+    file_metadata: &fs::Metadata,
+    limits: &IngestLimits,
+) -> Result<ImageFile, SkipReason> {
+    let dimensions =
+        image_dimensions::fetch_image_dimensions(file_name).map_err(SkipReason::UnreadableImage)?;
+    if limits.exceeds_megapixels(dimensions) {
+        return Err(SkipReason::TooManyPixels {
+            width: dimensions.0,
+            height: dimensions.1,
+            max_megapixels: limits.max_megapixels,
+        });
+    }
+    let unreadable = |e: io::Error| {
+        SkipReason::UnreadableImage(FetchImageDimensionsError::ErrorOpeningImage(e.to_string()))
+    };
+    let content_hash = content_hash::fetch_content_hash(file_name).map_err(unreadable)?;
+    let path = Path::new(IMAGES_DIR).join(file_name);
+    let format = image_formats::sniff_format(&path).map_err(unreadable)?;
+    let metadata = image_metadata::fetch_image_metadata(file_name);
+    let (width, height) = image_orientation::oriented_dimensions(dimensions, metadata.orientation);
+    let perceptual_hash =
+        perceptual_hash::fetch_perceptual_hash(file_name, metadata.orientation, limits);
+    Ok(ImageFile {
+        image: Image {
+            file_name: file_name.to_string(),
+            width,
+            height,
+        },
+        metadata,
+        content_hash,
+        perceptual_hash,
+        format: format.map(|f| image_formats::format_name(f).to_string()),
+        mime_type: format.map(|f| f.to_mime_type().to_string()),
+        byte_size: file_metadata.len(),
+        modified_at: modified_at(file_metadata),
     })
 }
 
@@ -95,6 +412,8 @@ fn fetch_dimensions(file_name: &str) -> Result<Image, FetchDimensionsError> {
 pub enum UpdateCanonError {
     FetchCanonError(FetchCanonError),
     FailedToUpdateCanon(String),
+    /// The update stopped before it finished, like when it panicked.
+    Interrupted(String),
 }
 
 impl From<FetchCanonError> for UpdateCanonError {
@@ -103,21 +422,322 @@ impl From<FetchCanonError> for UpdateCanonError {
     }
 }
 
+/// Saves the images on disk and syncs the derivatives of those that changed, then merges them
+/// into the screensaver, leaving out the images excluded from it. Only the files that changed since they were last read are read again.
+/// Returns the entries left out of the canon, which are only reported when the `mode` isn't
+/// [CanonMode::Strict].
 pub async fn update_canon(
-    uc: &impl UpdateCanon,
+    uc: &(impl UpdateCanon + FetchImageFingerprints + FetchScreensaverExclusions),
     screensaver: &mut impl Screensaver,
-) -> Result<(), UpdateCanonError> {
-    let images = fetch_canon()?;
-    uc.update_canon(images.iter())
+    mode: CanonMode,
+    limits: &IngestLimits,
+) -> Result<Vec<SkippedEntry>, UpdateCanonError> {
+    let FetchedCanon {
+        images,
+        unchanged,
+        skipped,
+    } = fetch_canon(mode, fetch_fingerprints(uc).await?, limits).await?;
+    log_skipped(&skipped);
+    tracing::debug!(
+        "read {} files of the canon, {} were unchanged",
+        images.len(),
+        unchanged.len()
+    );
+
+    let deleted = uc
+        .update_canon(images.iter(), &kept_file_names(&unchanged, &skipped))
         .await
         .map_err(UpdateCanonError::FailedToUpdateCanon)?;
 
-    screensaver.replace(
+    let read = images.iter().map(|i| i.image.file_name.clone()).collect();
+    sync_derivatives(read, deleted, limits).await;
+
+    let exclusions = uc
+        .fetch_screensaver_exclusions()
+        .await
+        .map_err(UpdateCanonError::FailedToUpdateCanon)?;
+    screensaver.merge(
         images
             .into_iter()
+            .map(|i| i.image)
+            .chain(unchanged)
+            .filter(|i| !exclusions.contains(&i.file_name))
             .map(|i| (i.file_name.clone(), i))
             .collect(),
     );
 
-    Ok(())
+    Ok(skipped)
+}
+
+/// Generates the derivatives of the images whose files were read, a few at a time like they're
+/// read, and removes those of the deleted images. Failures are logged, since derivatives are
+/// generated on demand when they're missing.
+async fn sync_derivatives(read: Vec<String>, deleted: Vec<String>, limits: &IngestLimits) {
+    let mut generated = stream::iter(read)
+        .map(|file_name| {
+            let (name, limits) = (file_name.clone(), limits.clone());
+            async move {
+                let res = tokio::task::spawn_blocking(move || {
+                    image_derivatives::generate_derivatives(&name, &limits)
+                })
+                .await;
+                (file_name, res)
+            }
+        })
+        .buffer_unordered(scan_parallelism());
+    while let Some((file_name, res)) = generated.next().await {
+        match res {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::warn!("failed to generate derivatives of {}: {:?}", file_name, e)
+            }
+            Err(e) => tracing::warn!("generating derivatives of {} panicked: {}", file_name, e),
+        }
+    }
+
+    let deleted = run_blocking(move || {
+        for file_name in deleted {
+            if let Err(e) = image_derivatives::delete_derivatives(&file_name) {
+                tracing::warn!("failed to delete derivatives of {}: {}", file_name, e);
+            }
+        }
+    })
+    .await;
+    if let Err(e) = deleted {
+        tracing::warn!("deleting derivatives panicked: {:?}", e);
+    }
+}
+
+/// The outcome of an update shared by the requests coalesced into it.
+pub type SharedUpdateOutcome = Arc<Result<Vec<SkippedEntry>, UpdateCanonError>>;
+type SharedUpdate = Shared<BoxFuture<'static, SharedUpdateOutcome>>;
+
+/// Coalesces overlapping canon updates. A request made while an update with the same mode runs
+/// gets the outcome of that update instead of starting another one. Updates with different
+/// modes run one after the other.
+#[derive(Clone, Default)]
+pub struct CanonUpdates {
+    running: Arc<Mutex<HashMap<CanonMode, SharedUpdate>>>,
+    exclusive: Arc<tokio::sync::Mutex<()>>,
+    limits: IngestLimits,
+}
+
+impl CanonUpdates {
+    /// The updates skip the images over the `limits`.
+    pub fn new(limits: IngestLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    /// Runs [update_canon], or joins the update with the same `mode` that's running. The update
+    /// runs on its own task, so it finishes even if every request waiting on it is dropped.
+    pub async fn update_canon(
+        &self,
+        uc: impl 'static
+            + Send
+            + Sync
+            + UpdateCanon
+            + FetchImageFingerprints
+            + FetchScreensaverExclusions,
+        mut screensaver: impl 'static + Send + Screensaver,
+        mode: CanonMode,
+    ) -> SharedUpdateOutcome {
+        let update = {
+            let mut running = self.acquire_lock();
+            if let Some(update) = running.get(&mode) {
+                tracing::debug!("joining the running {:?} canon update", mode);
+                update.clone()
+            } else {
+                let updates = self.clone();
+                let task = tokio::spawn(async move {
+                    let _running = RunningUpdate {
+                        updates: updates.clone(),
+                        mode,
+                    };
+                    let _exclusive = updates.exclusive.lock().await;
+                    let limits = updates.limits.clone();
+                    Arc::new(update_canon(&uc, &mut screensaver, mode, &limits).await)
+                });
+                let update = async move {
+                    task.await.unwrap_or_else(|e| {
+                        Arc::new(Err(UpdateCanonError::Interrupted(e.to_string())))
+                    })
+                }
+                .boxed()
+                .shared();
+                running.insert(mode, update.clone());
+                update
+            }
+        };
+
+        update.await
+    }
+
+    /// In this case, we don't care if the mutex is poisoned, as the map is only modified by
+    /// single inserts and removes.
+    fn acquire_lock(&self) -> MutexGuard<'_, HashMap<CanonMode, SharedUpdate>> {
+        match self.running.lock() {
+            Ok(guard) => guard,
+            Err(poison) => {
+                tracing::debug!("Accessing poisoned mutex");
+                poison.into_inner()
+            }
+        }
+    }
+}
+
+/// Removes the update of its mode from [CanonUpdates] when dropped, even if the update panicked,
+/// so later updates don't join it.
+struct RunningUpdate {
+    updates: CanonUpdates,
+    mode: CanonMode,
+}
+
+impl Drop for RunningUpdate {
+    fn drop(&mut self) {
+        self.updates.acquire_lock().remove(&self.mode);
+    }
+}
+
+/// Reports what [update_canon] would change in the saved images, without changing anything.
+/// Entries that would be quarantined are left in place.
+pub async fn diff_canon(
+    dc: &(impl DiffCanon + FetchImageFingerprints),
+    mode: CanonMode,
+    limits: &IngestLimits,
+) -> Result<(CanonDiff, Vec<SkippedEntry>), UpdateCanonError> {
+    let fetch_mode = match mode {
+        CanonMode::Quarantine => CanonMode::Skip,
+        mode => mode,
+    };
+    let FetchedCanon {
+        images,
+        unchanged,
+        skipped,
+    } = fetch_canon(fetch_mode, fetch_fingerprints(dc).await?, limits).await?;
+    log_skipped(&skipped);
+
+    // Quarantined entries are no longer in place, so the images saved under their names would
+    // be deleted.
+    let kept_file_names = match mode {
+        CanonMode::Quarantine => kept_file_names(&unchanged, &[]),
+        _ => kept_file_names(&unchanged, &skipped),
+    };
+    let diff = dc
+        .diff_canon(images.iter(), &kept_file_names)
+        .await
+        .map_err(UpdateCanonError::FailedToUpdateCanon)?;
+
+    Ok((diff, skipped))
+}
+
+fn log_skipped(skipped: &[SkippedEntry]) {
+    for entry in skipped {
+        tracing::warn!(
+            "left {:?} out of the canon: {:?}",
+            entry.file_name,
+            entry.reason
+        );
+        if let Some(quarantined_as) = &entry.quarantined_as {
+            tracing::warn!(
+                "moved {:?} to quarantine as {}",
+                entry.file_name,
+                quarantined_as
+            );
+        }
+    }
+}
+
+async fn fetch_fingerprints(
+    fif: &impl FetchImageFingerprints,
+) -> Result<HashMap<String, ImageFingerprint>, UpdateCanonError> {
+    Ok(fif
+        .fetch_image_fingerprints()
+        .await
+        .map_err(UpdateCanonError::FailedToUpdateCanon)?
+        .into_iter()
+        .map(|f| (f.image.file_name.clone(), f))
+        .collect())
+}
+
+/// The saved images of unchanged files are left as they are. So are those of skipped files that
+/// are still in place, since they may only be unreadable for now.
+fn kept_file_names(unchanged: &[Image], skipped: &[SkippedEntry]) -> HashSet<String> {
+    let skipped = skipped
+        .iter()
+        .filter(|e| e.quarantined_as.is_none())
+        .filter_map(|e| e.file_name.clone());
+    unchanged
+        .iter()
+        .map(|i| i.file_name.clone())
+        .chain(skipped)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::state::screensaver_manager::ScreensaverManager;
+
+    /// Panics when fetching fingerprints the first time, then fails.
+    #[derive(Clone, Default)]
+    struct PanicsOnce {
+        panicked: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl FetchImageFingerprints for PanicsOnce {
+        async fn fetch_image_fingerprints(&self) -> Result<Vec<ImageFingerprint>, String> {
+            if !self.panicked.swap(true, Ordering::SeqCst) {
+                panic!("fetching fingerprints panicked");
+            }
+            Err("fetching fingerprints failed".to_string())
+        }
+    }
+
+    #[async_trait]
+    impl UpdateCanon for PanicsOnce {
+        async fn update_canon<'a, T: Iterator<Item = &'a ImageFile> + Send>(
+            &self,
+            _: T,
+            _: &HashSet<String>,
+        ) -> Result<Vec<String>, String> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[async_trait]
+    impl FetchScreensaverExclusions for PanicsOnce {
+        async fn fetch_screensaver_exclusions(&self) -> Result<HashSet<String>, String> {
+            Ok(HashSet::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn update_after_panicked_update_runs_again() {
+        // Arrange
+        let sut = CanonUpdates::default();
+        let uc = PanicsOnce::default();
+        let screensaver = ScreensaverManager::restore(Vec::new(), HashMap::new());
+        let panicked = sut
+            .update_canon(uc.clone(), screensaver.clone(), CanonMode::Skip)
+            .await;
+
+        // Act
+        let res = sut.update_canon(uc, screensaver, CanonMode::Skip).await;
+
+        // Assert
+        assert!(matches!(*panicked, Err(UpdateCanonError::Interrupted(_))));
+        assert!(matches!(
+            *res,
+            Err(UpdateCanonError::FailedToUpdateCanon(_))
+        ));
+        assert!(sut.acquire_lock().is_empty());
+    }
 }
@@ -0,0 +1,21 @@
+use std::{fs::File, io, path::Path};
+
+use sha2::{Digest, Sha256};
+
+use crate::api::IMAGES_DIR;
+
+/// Returns the hex encoded SHA-256 of the image file.
+pub fn fetch_content_hash(file_name: &str) -> io::Result<String> {
+    fetch_content_hash_at(&Path::new(IMAGES_DIR).join(file_name))
+}
+
+/// Like [fetch_content_hash], for a file outside of [IMAGES_DIR].
+pub fn fetch_content_hash_at(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(to_hex(hasher))
+}
+
+pub fn to_hex(hasher: Sha256) -> String {
+    format!("{:x}", hasher.finalize())
+}
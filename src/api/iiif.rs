@@ -0,0 +1,456 @@
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use serde::Serialize;
+
+/// The largest width or height an output image may have.
+pub const MAX_DIMENSION: u32 = 8192;
+
+/// An image request following the IIIF Image API 3.0 URL syntax:
+/// `{region}/{size}/{rotation}/{quality}.{format}`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct IiifRequest {
+    region: Region,
+    size: Size,
+    rotation: Rotation,
+    quality: Quality,
+    pub format: IiifFormat,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Region {
+    Full,
+    /// The largest centered square.
+    Square,
+    Pixels {
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+    },
+    Percent {
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Size {
+    kind: SizeKind,
+    /// Whether the output may be larger than the region, written as a leading `^`.
+    upscale: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SizeKind {
+    Max,
+    Width(u32),
+    Height(u32),
+    Percent(f64),
+    Exact(u32, u32),
+    /// The largest size that fits within the dimensions, keeping the aspect ratio.
+    BestFit(u32, u32),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Rotation {
+    /// Whether to mirror horizontally before rotating, written as a leading `!`.
+    mirror: bool,
+    /// Clockwise, in degrees. Only multiples of 90 are supported.
+    degrees: u16,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Quality {
+    Default,
+    Color,
+    Gray,
+    Bitonal,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IiifFormat {
+    Jpg,
+    Png,
+    Gif,
+    Webp,
+}
+
+impl IiifFormat {
+    pub fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Jpg => ImageFormat::Jpeg,
+            Self::Png => ImageFormat::Png,
+            Self::Gif => ImageFormat::Gif,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IiifError {
+    InvalidRegion(String),
+    InvalidSize(String),
+    InvalidRotation(String),
+    InvalidQuality(String),
+    InvalidFormat(String),
+    RegionOutOfBounds,
+    EmptySize,
+    SizeTooLarge { max_dimension: u32 },
+    UpscaleNotRequested,
+}
+
+impl IiifRequest {
+    pub fn parse(
+        region: &str,
+        size: &str,
+        rotation: &str,
+        quality_format: &str,
+    ) -> Result<Self, IiifError> {
+        let (quality, format) = quality_format
+            .rsplit_once('.')
+            .ok_or_else(|| IiifError::InvalidFormat(quality_format.to_string()))?;
+
+        Ok(Self {
+            region: parse_region(region)?,
+            size: parse_size(size)?,
+            rotation: parse_rotation(rotation)?,
+            quality: parse_quality(quality)?,
+            format: parse_format(format)?,
+        })
+    }
+
+    /// Applies the request to the image in the order required by the spec:
+    /// region, size, rotation, then quality.
+    pub fn apply(&self, image: DynamicImage) -> Result<DynamicImage, IiifError> {
+        let (x, y, w, h) = self.region.resolve(image.width(), image.height())?;
+        let image = if (x, y, w, h) == (0, 0, image.width(), image.height()) {
+            image
+        } else {
+            image.crop_imm(x, y, w, h)
+        };
+
+        let (w, h) = self.size.resolve(w, h)?;
+        let image = if (w, h) == (image.width(), image.height()) {
+            image
+        } else {
+            image.resize_exact(w, h, FilterType::Lanczos3)
+        };
+
+        let image = if self.rotation.mirror {
+            image.fliph()
+        } else {
+            image
+        };
+        let image = match self.rotation.degrees {
+            90 => image.rotate90(),
+            180 => image.rotate180(),
+            270 => image.rotate270(),
+            _ => image,
+        };
+
+        Ok(match self.quality {
+            Quality::Default | Quality::Color => image,
+            Quality::Gray => DynamicImage::ImageLuma8(image.to_luma8()),
+            Quality::Bitonal => {
+                let mut luma = image.to_luma8();
+                for pixel in luma.pixels_mut() {
+                    pixel.0[0] = if pixel.0[0] < 128 { 0 } else { 255 };
+                }
+                DynamicImage::ImageLuma8(luma)
+            }
+        })
+    }
+}
+
+impl Region {
+    /// Returns the region as `(x, y, w, h)` within an image of the given dimensions.
+    /// Regions extending past the image are cropped to it.
+    fn resolve(self, width: u32, height: u32) -> Result<(u32, u32, u32, u32), IiifError> {
+        let (x, y, w, h) = match self {
+            Self::Full => (0, 0, width, height),
+            Self::Square => {
+                let side = width.min(height);
+                ((width - side) / 2, (height - side) / 2, side, side)
+            }
+            Self::Pixels { x, y, w, h } => (x, y, w, h),
+            Self::Percent { x, y, w, h } => {
+                let scale = |v: f64, of: u32| (v / 100.0 * of as f64).round() as u32;
+                (
+                    scale(x, width),
+                    scale(y, height),
+                    scale(w, width),
+                    scale(h, height),
+                )
+            }
+        };
+        if x >= width || y >= height {
+            return Err(IiifError::RegionOutOfBounds);
+        }
+
+        let (w, h) = (w.min(width - x), h.min(height - y));
+        if w == 0 || h == 0 {
+            return Err(IiifError::RegionOutOfBounds);
+        }
+        Ok((x, y, w, h))
+    }
+}
+
+impl Size {
+    /// Returns the output dimensions for a region of the given dimensions.
+    fn resolve(self, width: u32, height: u32) -> Result<(u32, u32), IiifError> {
+        let scale = |v: u32, num: u32, denom: u32| {
+            (v as f64 * num as f64 / denom as f64).round().max(1.0) as u32
+        };
+        let (w, h) = match self.kind {
+            SizeKind::Max => {
+                let largest = width.max(height);
+                if largest > MAX_DIMENSION {
+                    (
+                        scale(width, MAX_DIMENSION, largest),
+                        scale(height, MAX_DIMENSION, largest),
+                    )
+                } else {
+                    (width, height)
+                }
+            }
+            SizeKind::Width(w) => (w, scale(height, w, width)),
+            SizeKind::Height(h) => (scale(width, h, height), h),
+            SizeKind::Percent(p) => {
+                let scale = |v: u32| (v as f64 * p / 100.0).round() as u32;
+                (scale(width), scale(height))
+            }
+            SizeKind::Exact(w, h) => (w, h),
+            SizeKind::BestFit(w, h) => {
+                // Compare the aspect ratios to find which side limits the size.
+                if w as u64 * height as u64 <= h as u64 * width as u64 {
+                    (w, scale(height, w, width))
+                } else {
+                    (scale(width, h, height), h)
+                }
+            }
+        };
+
+        if w == 0 || h == 0 {
+            return Err(IiifError::EmptySize);
+        }
+        if w > MAX_DIMENSION || h > MAX_DIMENSION {
+            return Err(IiifError::SizeTooLarge {
+                max_dimension: MAX_DIMENSION,
+            });
+        }
+        if !self.upscale && (w > width || h > height) {
+            return Err(IiifError::UpscaleNotRequested);
+        }
+        Ok((w, h))
+    }
+}
+
+fn parse_region(value: &str) -> Result<Region, IiifError> {
+    let err = || IiifError::InvalidRegion(value.to_string());
+    match value {
+        "full" => Ok(Region::Full),
+        "square" => Ok(Region::Square),
+        _ => {
+            if let Some(percent) = value.strip_prefix("pct:") {
+                let [x, y, w, h] = parse_list::<f64, 4>(percent).ok_or_else(err)?;
+                if [x, y, w, h].iter().any(|v| !v.is_finite() || *v < 0.0) {
+                    return Err(err());
+                }
+                Ok(Region::Percent { x, y, w, h })
+            } else {
+                let [x, y, w, h] = parse_list::<u32, 4>(value).ok_or_else(err)?;
+                Ok(Region::Pixels { x, y, w, h })
+            }
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Result<Size, IiifError> {
+    let err = || IiifError::InvalidSize(value.to_string());
+    let (upscale, rest) = match value.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+
+    let kind = if rest == "max" {
+        SizeKind::Max
+    } else if let Some(percent) = rest.strip_prefix("pct:") {
+        let percent: f64 = percent.parse().map_err(|_| err())?;
+        if !percent.is_finite() || percent <= 0.0 {
+            return Err(err());
+        }
+        SizeKind::Percent(percent)
+    } else if let Some(best_fit) = rest.strip_prefix('!') {
+        let [w, h] = parse_list::<u32, 2>(best_fit).ok_or_else(err)?;
+        SizeKind::BestFit(w, h)
+    } else {
+        let (w, h) = rest.split_once(',').ok_or_else(err)?;
+        match (w, h) {
+            ("", "") => return Err(err()),
+            (w, "") => SizeKind::Width(w.parse().map_err(|_| err())?),
+            ("", h) => SizeKind::Height(h.parse().map_err(|_| err())?),
+            (w, h) => SizeKind::Exact(w.parse().map_err(|_| err())?, h.parse().map_err(|_| err())?),
+        }
+    };
+
+    Ok(Size { kind, upscale })
+}
+
+fn parse_rotation(value: &str) -> Result<Rotation, IiifError> {
+    let err = || IiifError::InvalidRotation(value.to_string());
+    let (mirror, degrees) = match value.strip_prefix('!') {
+        Some(degrees) => (true, degrees),
+        None => (false, value),
+    };
+    let degrees: u16 = degrees.parse().map_err(|_| err())?;
+    if !matches!(degrees, 0 | 90 | 180 | 270) {
+        return Err(err());
+    }
+
+    Ok(Rotation { mirror, degrees })
+}
+
+fn parse_quality(value: &str) -> Result<Quality, IiifError> {
+    match value {
+        "default" => Ok(Quality::Default),
+        "color" => Ok(Quality::Color),
+        "gray" => Ok(Quality::Gray),
+        "bitonal" => Ok(Quality::Bitonal),
+        _ => Err(IiifError::InvalidQuality(value.to_string())),
+    }
+}
+
+fn parse_format(value: &str) -> Result<IiifFormat, IiifError> {
+    match value {
+        "jpg" => Ok(IiifFormat::Jpg),
+        "png" => Ok(IiifFormat::Png),
+        "gif" => Ok(IiifFormat::Gif),
+        "webp" => Ok(IiifFormat::Webp),
+        _ => Err(IiifError::InvalidFormat(value.to_string())),
+    }
+}
+
+/// Parses exactly `N` comma separated values.
+fn parse_list<T: std::str::FromStr, const N: usize>(value: &str) -> Option<[T; N]> {
+    let values: Vec<T> = value
+        .split(',')
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView;
+
+    use super::*;
+
+    fn parse(region: &str, size: &str) -> IiifRequest {
+        IiifRequest::parse(region, size, "0", "default.jpg").expect("request should be valid")
+    }
+
+    #[test]
+    fn parses_full_request() {
+        // Act
+        let res = IiifRequest::parse("pct:10,20,30,40", "^!100,50", "!90", "gray.png");
+
+        // Assert
+        assert_eq!(
+            Ok(IiifRequest {
+                region: Region::Percent {
+                    x: 10.0,
+                    y: 20.0,
+                    w: 30.0,
+                    h: 40.0
+                },
+                size: Size {
+                    kind: SizeKind::BestFit(100, 50),
+                    upscale: true
+                },
+                rotation: Rotation {
+                    mirror: true,
+                    degrees: 90
+                },
+                quality: Quality::Gray,
+                format: IiifFormat::Png,
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn rejects_arbitrary_rotation() {
+        // Act
+        let res = IiifRequest::parse("full", "max", "45", "default.jpg");
+
+        // Assert
+        assert_eq!(Err(IiifError::InvalidRotation("45".to_string())), res);
+    }
+
+    #[test]
+    fn region_is_cropped_to_image() {
+        // Act
+        let res = Region::Pixels {
+            x: 50,
+            y: 0,
+            w: 100,
+            h: 100,
+        }
+        .resolve(80, 60);
+
+        // Assert
+        assert_eq!(Ok((50, 0, 30, 60)), res);
+    }
+
+    #[test]
+    fn region_outside_image_is_error() {
+        // Act
+        let res = Region::Pixels {
+            x: 80,
+            y: 0,
+            w: 10,
+            h: 10,
+        }
+        .resolve(80, 60);
+
+        // Assert
+        assert_eq!(Err(IiifError::RegionOutOfBounds), res);
+    }
+
+    #[test]
+    fn best_fit_keeps_aspect_ratio() {
+        // Act
+        let res = parse("full", "!100,100").size.resolve(400, 200);
+
+        // Assert
+        assert_eq!(Ok((100, 50)), res);
+    }
+
+    #[test]
+    fn larger_size_requires_upscale() {
+        // Act
+        let res = parse("full", "800,").size.resolve(400, 200);
+        let upscaled = parse("full", "^800,").size.resolve(400, 200);
+
+        // Assert
+        assert_eq!(Err(IiifError::UpscaleNotRequested), res);
+        assert_eq!(Ok((800, 400)), upscaled);
+    }
+
+    #[test]
+    fn apply_crops_resizes_and_rotates() {
+        // Arrange
+        let sut = IiifRequest::parse("square", "50,", "90", "default.png")
+            .expect("request should be valid");
+
+        // Act
+        let res = sut
+            .apply(DynamicImage::new_rgb8(200, 100))
+            .expect("request should apply");
+
+        // Assert
+        assert_eq!((50, 50), res.dimensions());
+    }
+}
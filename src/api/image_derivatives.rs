@@ -0,0 +1,205 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    image_metadata, image_orientation,
+    ingest_limits::{IngestLimits, OpenImageError},
+    DERIVATIVES_DIR, IMAGES_DIR,
+};
+
+const JPEG_QUALITY: u8 = 85;
+
+/// The fixed sizes derivatives are generated at. Every derivative is a JPEG with the EXIF
+/// orientation of its image applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DerivativeSize {
+    Thumbnail,
+    Small,
+    Preview,
+}
+
+impl DerivativeSize {
+    /// Ordered from largest to smallest, so smaller sizes can be resized from larger ones.
+    pub const ALL: [Self; 3] = [Self::Preview, Self::Small, Self::Thumbnail];
+
+    /// The maximum width and height of the derivative.
+    fn max_dimension(self) -> u32 {
+        match self {
+            Self::Thumbnail => 256,
+            Self::Small => 800,
+            Self::Preview => 1600,
+        }
+    }
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Thumbnail => "thumbnail",
+            Self::Small => "small",
+            Self::Preview => "preview",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GenerateDerivativesError {
+    ErrorOpeningImage(String),
+    #[serde(rename_all = "camelCase")]
+    TooManyPixels {
+        width: u32,
+        height: u32,
+        max_megapixels: f64,
+    },
+    FailedToDecodeImage(String),
+    FailedToWriteDerivative(String),
+}
+
+pub fn derivative_path(size: DerivativeSize, file_name: &str) -> PathBuf {
+    Path::new(DERIVATIVES_DIR)
+        .join(size.dir_name())
+        .join(format!("{}.jpg", file_name))
+}
+
+/// Generates every derivative of the image that is missing or older than the image itself.
+/// Images over the `limits` aren't decoded.
+pub fn generate_derivatives(
+    file_name: &str,
+    limits: &IngestLimits,
+) -> Result<(), GenerateDerivativesError> {
+    let path = Path::new(IMAGES_DIR).join(file_name);
+    let modified = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map_err(|e| GenerateDerivativesError::ErrorOpeningImage(e.to_string()))?;
+    let stale: Vec<_> = DerivativeSize::ALL
+        .into_iter()
+        .filter(|&size| !is_fresh(&derivative_path(size, file_name), modified))
+        .collect();
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    let image = limits
+        .open_within(&path)
+        .map_err(|e| match e {
+            OpenImageError::Unreadable(e) => {
+                GenerateDerivativesError::ErrorOpeningImage(format!("{:?}", e))
+            }
+            OpenImageError::TooManyPixels {
+                width,
+                height,
+                max_megapixels,
+            } => GenerateDerivativesError::TooManyPixels {
+                width,
+                height,
+                max_megapixels,
+            },
+        })?
+        .decode()
+        .map_err(|e| GenerateDerivativesError::FailedToDecodeImage(e.to_string()))?;
+    let orientation = image_metadata::fetch_image_metadata(file_name).orientation;
+    let mut image = image_orientation::orient_image(image, orientation);
+
+    for size in stale {
+        image = fit_within(image, size.max_dimension());
+        write_jpeg(&image, &derivative_path(size, file_name))
+            .map_err(|e| GenerateDerivativesError::FailedToWriteDerivative(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Removes every derivative of the image. Missing derivatives are ignored.
+pub fn delete_derivatives(file_name: &str) -> io::Result<()> {
+    for size in DerivativeSize::ALL {
+        ignore_not_found(fs::remove_file(derivative_path(size, file_name)))?;
+    }
+    Ok(())
+}
+
+/// Moves every derivative of the image to its new name. Missing derivatives are ignored.
+pub fn rename_derivatives(old_name: &str, new_name: &str) -> io::Result<()> {
+    for size in DerivativeSize::ALL {
+        ignore_not_found(fs::rename(
+            derivative_path(size, old_name),
+            derivative_path(size, new_name),
+        ))?;
+    }
+    Ok(())
+}
+
+fn ignore_not_found(res: io::Result<()>) -> io::Result<()> {
+    match res {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn is_fresh(derivative: &Path, image_modified: SystemTime) -> bool {
+    fs::metadata(derivative)
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| modified >= image_modified)
+}
+
+/// Scales the image down to fit within a square of `max_dimension`. Smaller images are kept.
+fn fit_within(image: DynamicImage, max_dimension: u32) -> DynamicImage {
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        image
+    } else {
+        image.thumbnail(max_dimension, max_dimension)
+    }
+}
+
+/// Writes to a temporary file first, so a derivative is never served half written.
+fn write_jpeg(image: &DynamicImage, path: &Path) -> Result<(), String> {
+    let dir = path.parent().ok_or("derivative path has no parent")?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+    let tmp_path = dir.join(format!(".{:016x}.tmp", rand::random::<u64>()));
+    let res = File::create(&tmp_path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY)
+                .encode_image(&image.to_rgb8())
+                .map_err(|e| e.to_string())
+        })
+        .and_then(|_| fs::rename(&tmp_path, path).map_err(|e| e.to_string()));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView;
+
+    use super::*;
+
+    #[test]
+    fn fit_within_keeps_aspect_ratio() {
+        // Act
+        let res = fit_within(DynamicImage::new_rgb8(400, 100), 200);
+
+        // Assert
+        assert_eq!((200, 50), res.dimensions());
+    }
+
+    #[test]
+    fn fit_within_does_not_upscale() {
+        // Act
+        let res = fit_within(DynamicImage::new_rgb8(40, 10), 200);
+
+        // Assert
+        assert_eq!((40, 10), res.dimensions());
+    }
+}
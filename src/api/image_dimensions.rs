@@ -1,9 +1,11 @@
+use std::path::Path;
+
 use image::io::Reader as ImageReader;
 use serde::Serialize;
 
 use crate::api::IMAGES_DIR;
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum FetchImageDimensionsError {
     ErrorOpeningImage(String),
@@ -11,8 +13,14 @@ pub enum FetchImageDimensionsError {
 }
 
 pub fn fetch_image_dimensions(file_name: &str) -> Result<(u32, u32), FetchImageDimensionsError> {
-    let path = std::path::Path::new(IMAGES_DIR).join(file_name);
+    fetch_image_dimensions_at(&Path::new(IMAGES_DIR).join(file_name))
+}
+
+/// Like [fetch_image_dimensions], for an image outside of [IMAGES_DIR].
+pub fn fetch_image_dimensions_at(path: &Path) -> Result<(u32, u32), FetchImageDimensionsError> {
+    // The format is guessed from the content first, so images with the wrong extension are read.
     let image = ImageReader::open(path)
+        .and_then(|r| r.with_guessed_format())
         .map_err(|e| FetchImageDimensionsError::ErrorOpeningImage(e.to_string()))?;
     let dim = image
         .into_dimensions()
@@ -0,0 +1,120 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read},
+    path::Path,
+};
+
+use image::{
+    codecs::{
+        jpeg::JpegEncoder,
+        webp::{WebPEncoder, WebPQuality},
+    },
+    DynamicImage, ImageFormat, ImageResult,
+};
+
+/// Enough bytes to hold the magic bytes of every format.
+const SNIFF_LEN: u64 = 64;
+
+const JPEG_QUALITY: u8 = 85;
+const WEBP_QUALITY: u8 = 80;
+
+/// Detects the format of the image file from its magic bytes, ignoring its extension.
+/// Returns `None` when the format isn't recognized.
+pub fn sniff_format(path: &Path) -> io::Result<Option<ImageFormat>> {
+    let mut header = Vec::new();
+    File::open(path)?.take(SNIFF_LEN).read_to_end(&mut header)?;
+    Ok(image::guess_format(&header).ok())
+}
+
+/// Returns the name the format is stored and configured by, which is its main extension.
+pub fn format_name(format: ImageFormat) -> &'static str {
+    format.extensions_str().first().copied().unwrap_or_default()
+}
+
+/// Returns `true` for the formats every client can display, including the Roku.
+pub fn is_widely_supported(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Gif
+    )
+}
+
+/// Returns `true` if the `Accept` header explicitly lists `mime` without a quality of 0.
+/// Wildcards are ignored, since clients like the Roku send `*/*` even for formats they can't
+/// display.
+pub fn accepts(accept: Option<&str>, mime: &str) -> bool {
+    accept.into_iter().flat_map(|a| a.split(',')).any(|range| {
+        let mut params = range.split(';').map(str::trim);
+        let media_range = params.next().unwrap_or_default();
+        let is_rejected = params.any(|p| {
+            p.strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        media_range.eq_ignore_ascii_case(mime) && !is_rejected
+    })
+}
+
+/// Encodes the image, dropping its alpha channel for formats without one.
+pub fn encode(image: &DynamicImage, format: ImageFormat) -> ImageResult<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY)
+            .encode_image(&image.to_rgb8())?,
+        ImageFormat::WebP => {
+            let encoder =
+                WebPEncoder::new_with_quality(&mut bytes, WebPQuality::lossy(WEBP_QUALITY));
+            if image.color().has_alpha() {
+                let image = image.to_rgba8();
+                encoder.encode(
+                    &image,
+                    image.width(),
+                    image.height(),
+                    image::ColorType::Rgba8,
+                )?
+            } else {
+                let image = image.to_rgb8();
+                encoder.encode(
+                    &image,
+                    image.width(),
+                    image.height(),
+                    image::ColorType::Rgb8,
+                )?
+            }
+        }
+        _ => image.write_to(&mut bytes, format)?,
+    }
+
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_name_is_main_extension() {
+        assert_eq!("jpg", format_name(ImageFormat::Jpeg));
+        assert_eq!("png", format_name(ImageFormat::Png));
+    }
+
+    #[test]
+    fn accepts_listed_mime() {
+        assert!(accepts(
+            Some("image/avif,image/webp,*/*;q=0.8"),
+            "image/webp"
+        ));
+    }
+
+    #[test]
+    fn wildcard_does_not_accept_mime() {
+        assert!(!accepts(Some("*/*"), "image/webp"));
+        assert!(!accepts(Some("image/*"), "image/webp"));
+        assert!(!accepts(None, "image/webp"));
+    }
+
+    #[test]
+    fn zero_quality_rejects_mime() {
+        assert!(!accepts(Some("image/webp;q=0, image/png"), "image/webp"));
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use chrono::NaiveDateTime;
+use exif::{Exif, In, Reader, Tag, Value};
+
+use crate::{api::IMAGES_DIR, domain::models::ImageMetadata};
+
+/// Reads the EXIF metadata of an image.
+/// Images without readable EXIF have empty metadata, since EXIF is optional.
+pub fn fetch_image_metadata(file_name: &str) -> ImageMetadata {
+    fetch_image_metadata_at(&Path::new(IMAGES_DIR).join(file_name))
+}
+
+/// Like [fetch_image_metadata], for an image outside of [IMAGES_DIR].
+pub fn fetch_image_metadata_at(path: &Path) -> ImageMetadata {
+    let exif = File::open(path)
+        .map_err(exif::Error::from)
+        .and_then(|file| {
+            let mut reader = BufReader::new(file);
+            Reader::new().read_from_container(&mut reader)
+        });
+
+    match exif {
+        Ok(exif) => metadata_from(&exif),
+        Err(e) => {
+            tracing::debug!("no EXIF read from {}: {}", path.display(), e);
+            ImageMetadata::default()
+        }
+    }
+}
+
+fn metadata_from(exif: &Exif) -> ImageMetadata {
+    let field = |tag| exif.get_field(tag, In::PRIMARY).map(|f| &f.value);
+
+    ImageMetadata {
+        taken_at: field(Tag::DateTimeOriginal)
+            .or_else(|| field(Tag::DateTime))
+            .and_then(ascii)
+            .and_then(|s| parse_date_time(&s)),
+        camera_make: field(Tag::Make).and_then(ascii),
+        camera_model: field(Tag::Model).and_then(ascii),
+        lens_model: field(Tag::LensModel).and_then(ascii),
+        exposure_time: field(Tag::ExposureTime).and_then(|v| rational(v, 0)),
+        f_number: field(Tag::FNumber).and_then(|v| rational(v, 0)),
+        iso: field(Tag::PhotographicSensitivity).and_then(|v| v.get_uint(0)),
+        focal_length: field(Tag::FocalLength).and_then(|v| rational(v, 0)),
+        orientation: field(Tag::Orientation)
+            .and_then(|v| v.get_uint(0))
+            .and_then(|v| u16::try_from(v).ok())
+            .filter(|v| (1..=8).contains(v)),
+        gps_latitude: gps_coordinate(field(Tag::GPSLatitude), field(Tag::GPSLatitudeRef), b'S'),
+        gps_longitude: gps_coordinate(field(Tag::GPSLongitude), field(Tag::GPSLongitudeRef), b'W'),
+    }
+}
+
+fn ascii(value: &Value) -> Option<String> {
+    match value {
+        Value::Ascii(v) => v
+            .first()
+            .map(|s| String::from_utf8_lossy(s).trim().to_string())
+            .filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+fn rational(value: &Value, index: usize) -> Option<f64> {
+    match value {
+        Value::Rational(v) => v.get(index).filter(|r| r.denom != 0).map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// Converts degrees, minutes and seconds to decimal degrees,
+/// negated when the reference is `negative_ref`.
+fn gps_coordinate(dms: Option<&Value>, reference: Option<&Value>, negative_ref: u8) -> Option<f64> {
+    let dms = dms?;
+    let degrees = rational(dms, 0)? + rational(dms, 1)? / 60.0 + rational(dms, 2)? / 3600.0;
+    let is_negative = reference
+        .and_then(ascii)
+        .is_some_and(|r| r.as_bytes().first() == Some(&negative_ref));
+
+    Some(if is_negative { -degrees } else { degrees })
+}
+
+/// EXIF date times look like `2019:07:04 13:37:00`.
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y:%m:%d %H:%M:%S").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use exif::Rational;
+
+    use super::*;
+
+    #[test]
+    fn parses_exif_date_time() {
+        // Act
+        let res = parse_date_time("2019:07:04 13:37:00");
+
+        // Assert
+        let expected = NaiveDateTime::parse_from_str("2019-07-04 13:37:00", "%Y-%m-%d %H:%M:%S")
+            .expect("expected should be valid");
+        assert_eq!(res, Some(expected));
+    }
+
+    #[test]
+    fn blank_date_time_is_none() {
+        // Act
+        let res = parse_date_time("    :  :     :  :  ");
+
+        // Assert
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn gps_coordinate_is_negative_for_negative_ref() {
+        // Arrange
+        let r = |num, denom| Rational { num, denom };
+        let dms = Value::Rational(vec![r(33, 1), r(30, 1), r(36, 10)]);
+        let reference = Value::Ascii(vec![b"S".to_vec()]);
+
+        // Act
+        let res =
+            gps_coordinate(Some(&dms), Some(&reference), b'S').expect("coordinate should be valid");
+
+        // Assert
+        assert!((res + 33.501).abs() < 1e-9);
+    }
+}
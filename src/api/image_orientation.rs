@@ -0,0 +1,84 @@
+use image::DynamicImage;
+
+/// Returns the dimensions of an image as displayed with the EXIF `orientation` applied.
+/// Orientations 5 through 8 rotate the image by a quarter turn, which swaps its dimensions.
+pub fn oriented_dimensions((width, height): (u32, u32), orientation: Option<u16>) -> (u32, u32) {
+    match orientation {
+        Some(5..=8) => (height, width),
+        _ => (width, height),
+    }
+}
+
+/// Returns `true` if serving the image as displayed requires transforming its pixels.
+pub fn needs_orienting(orientation: Option<u16>) -> bool {
+    matches!(orientation, Some(2..=8))
+}
+
+/// Transforms the pixels of `image` so that it displays correctly without its EXIF orientation.
+pub fn orient_image(image: DynamicImage, orientation: Option<u16>) -> DynamicImage {
+    match orientation {
+        Some(2) => image.fliph(),
+        Some(3) => image.rotate180(),
+        Some(4) => image.flipv(),
+        Some(5) => image.rotate90().fliph(),
+        Some(6) => image.rotate90(),
+        Some(7) => image.rotate270().fliph(),
+        Some(8) => image.rotate270(),
+        _ => image,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    use super::*;
+
+    /// A 2x1 image with a red pixel on the left and a blue pixel on the right.
+    fn mk_img() -> DynamicImage {
+        let mut img = RgbImage::new(2, 1);
+        img.put_pixel(0, 0, Rgb([255, 0, 0]));
+        img.put_pixel(1, 0, Rgb([0, 0, 255]));
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn quarter_turns_swap_dimensions() {
+        for orientation in 5..=8 {
+            assert_eq!((3, 2), oriented_dimensions((2, 3), Some(orientation)));
+        }
+    }
+
+    #[test]
+    fn other_orientations_keep_dimensions() {
+        assert_eq!((2, 3), oriented_dimensions((2, 3), None));
+        for orientation in 1..=4 {
+            assert_eq!((2, 3), oriented_dimensions((2, 3), Some(orientation)));
+        }
+    }
+
+    #[test]
+    fn orientation_6_rotates_clockwise() {
+        // Act
+        let res = orient_image(mk_img(), Some(6));
+
+        // Assert
+        assert_eq!((1, 2), res.dimensions());
+        assert_eq!([255, 0, 0], res.to_rgb8().get_pixel(0, 0).0);
+        assert_eq!([0, 0, 255], res.to_rgb8().get_pixel(0, 1).0);
+    }
+
+    #[test]
+    fn orientation_dimensions_match_oriented_pixels() {
+        for orientation in 1..=8 {
+            // Act
+            let res = orient_image(mk_img(), Some(orientation));
+
+            // Assert
+            assert_eq!(
+                oriented_dimensions((2, 1), Some(orientation)),
+                res.dimensions()
+            );
+        }
+    }
+}
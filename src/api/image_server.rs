@@ -1,8 +1,377 @@
-use axum::Router;
-use tower_http::services::ServeDir;
+use std::{fs, os::unix::fs::MetadataExt};
 
-use crate::api::IMAGES_DIR;
+use axum::{
+    body::{boxed, Body},
+    extract::{Path, Query, TypedHeader},
+    headers::Host,
+    http::{header, HeaderValue, Request},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use hyper::StatusCode;
+use image::{io::Reader as ImageReader, ImageFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
 
-pub fn create_image_server_router() -> Router {
-    Router::new().nest_service("/image", ServeDir::new(IMAGES_DIR))
+use crate::{
+    api::{
+        iiif::{self, IiifError, IiifRequest},
+        image_derivatives::{self, DerivativeSize, GenerateDerivativesError},
+        image_dimensions, image_formats, image_metadata, image_orientation,
+        ingest_limits::IngestLimits,
+        path_is_valid, IMAGES_DIR,
+    },
+    state::image_cache::{CachedFile, ImageCache},
+};
+
+pub fn create_image_server_router(cache: ImageCache, limits: IngestLimits) -> Router {
+    Router::new()
+        .nest(
+            "/image",
+            Router::new().route(
+                "/*file_name",
+                get({
+                    let cache = cache.clone();
+                    |path, query, request| serve_image(path, query, request, cache)
+                }),
+            ),
+        )
+        .route(
+            "/derivative/:size/:file_name",
+            get(|path, request| serve_derivative(path, request, limits)),
+        )
+        .route("/iiif/:file_name/info.json", get(serve_iiif_info))
+        .route(
+            "/iiif/:file_name/:region/:size/:rotation/:quality_format",
+            get(|path, request| serve_iiif(path, request, cache)),
+        )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServeImageInput {
+    /// Serve the pixels with the EXIF orientation applied, for clients that ignore it.
+    #[serde(default)]
+    oriented: bool,
+}
+
+/// Serves the image, converted when the client can't display its format.
+/// Clients that accept WebP receive it when it's smaller than the image.
+async fn serve_image(
+    Path(file_name): Path<String>,
+    Query(input): Query<ServeImageInput>,
+    request: Request<Body>,
+    cache: ImageCache,
+) -> Response {
+    let mut res = if path_is_valid(&file_name) {
+        let accept = request
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let converted = tokio::task::spawn_blocking(move || {
+            convert_for_client(&file_name, input.oriented, accept.as_deref(), &cache)
+        })
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        .and_then(|r| r);
+
+        match converted {
+            Ok(Some(file)) => serve_file(file, request).await,
+            Ok(None) => serve_original(request).await,
+            Err(e) => return e.into_response(),
+        }
+    } else {
+        serve_original(request).await
+    };
+
+    res.headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("accept"));
+    res
+}
+
+async fn serve_original(request: Request<Body>) -> Response {
+    match ServeDir::new(IMAGES_DIR).oneshot(request).await {
+        Ok(res) => res.map(boxed),
+        Err(e) => match e {},
+    }
+}
+
+/// Serves the cached file, which is held until it's opened so it can't be evicted before.
+async fn serve_file(file: CachedFile, request: Request<Body>) -> Response {
+    match ServeFile::new(file.path()).oneshot(request).await {
+        Ok(res) => res.map(boxed),
+        Err(e) => match e {},
+    }
+}
+
+/// Returns the converted image to serve, or `None` to serve the original.
+fn convert_for_client(
+    file_name: &str,
+    oriented: bool,
+    accept: Option<&str>,
+    cache: &ImageCache,
+) -> Result<Option<CachedFile>, (StatusCode, String)> {
+    let path = std::path::Path::new(IMAGES_DIR).join(file_name);
+    // Files that don't exist or aren't images are left to be served as they are.
+    let Ok(source) = fs::metadata(&path) else {
+        return Ok(None);
+    };
+    let Some(format) = ImageReader::open(&path)
+        .and_then(|r| r.with_guessed_format())
+        .ok()
+        .and_then(|r| r.format())
+    else {
+        return Ok(None);
+    };
+
+    let orientation = if oriented {
+        image_metadata::fetch_image_metadata(file_name).orientation
+    } else {
+        None
+    };
+    let needs_orienting = image_orientation::needs_orienting(orientation);
+    let original_is_displayable = !needs_orienting
+        && (image_formats::is_widely_supported(format)
+            || image_formats::accepts(accept, format.to_mime_type()));
+    // GIFs are kept since converting them would drop their animation.
+    let prefers_webp = image_formats::accepts(accept, ImageFormat::WebP.to_mime_type())
+        && !matches!(format, ImageFormat::WebP | ImageFormat::Gif);
+    if original_is_displayable && !prefers_webp {
+        return Ok(None);
+    }
+
+    let target = if prefers_webp {
+        ImageFormat::WebP
+    } else if image_formats::is_widely_supported(format) {
+        format
+    } else {
+        ImageFormat::Jpeg
+    };
+    let key = cache_key(
+        file_name,
+        &source,
+        &format!("convert/{:?}", orientation),
+        target,
+    );
+    let (converted, len) = match cache.get(&key) {
+        Some(converted) => {
+            let len = fs::metadata(converted.path())
+                .map(|m| m.len())
+                .unwrap_or(u64::MAX);
+            (converted, len)
+        }
+        None => {
+            let image = decode_oriented(file_name, orientation)?;
+            let bytes = image_formats::encode(&image, target)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            let converted = cache
+                .insert(&key, &bytes)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            (converted, bytes.len() as u64)
+        }
+    };
+
+    if original_is_displayable && len >= source.len() {
+        Ok(None)
+    } else {
+        Ok(Some(converted))
+    }
+}
+
+/// Returns a cache key unique to the output, changing whenever the image file does.
+fn cache_key(file_name: &str, source: &fs::Metadata, output: &str, format: ImageFormat) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "{}\n{}.{}\n{}\n{}",
+        file_name,
+        source.mtime(),
+        source.mtime_nsec(),
+        source.len(),
+        output,
+    ));
+    let extension = format.extensions_str().first().copied().unwrap_or("bin");
+    format!("{:x}.{}", hasher.finalize(), extension)
+}
+
+/// Decodes the image with its EXIF `orientation` applied.
+fn decode_oriented(
+    file_name: &str,
+    orientation: Option<u16>,
+) -> Result<image::DynamicImage, (StatusCode, String)> {
+    let path = std::path::Path::new(IMAGES_DIR).join(file_name);
+    let image = ImageReader::open(path)
+        .and_then(|r| r.with_guessed_format())
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?
+        .decode()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(image_orientation::orient_image(image, orientation))
+}
+
+/// Serves a derivative of the image, generating it first if it's missing.
+async fn serve_derivative(
+    Path((size, file_name)): Path<(DerivativeSize, String)>,
+    request: Request<Body>,
+    limits: IngestLimits,
+) -> Result<Response, (StatusCode, String)> {
+    if !path_is_valid(&file_name) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid file name".to_string()));
+    }
+
+    let path = image_derivatives::derivative_path(size, &file_name);
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        tokio::task::spawn_blocking(move || {
+            image_derivatives::generate_derivatives(&file_name, &limits)
+        })
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| match e {
+            GenerateDerivativesError::ErrorOpeningImage(e) => (StatusCode::NOT_FOUND, e),
+            e @ GenerateDerivativesError::TooManyPixels { .. } => {
+                (StatusCode::UNPROCESSABLE_ENTITY, format!("{:?}", e))
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)),
+        })?;
+    }
+
+    match ServeFile::new(path).oneshot(request).await {
+        Ok(res) => Ok(res.map(boxed)),
+        Err(e) => match e {},
+    }
+}
+
+/// Serves the image processed as described by a IIIF Image API request.
+/// Outputs are cached, keyed by the request and the size and modification time of the image.
+async fn serve_iiif(
+    Path((file_name, region, size, rotation, quality_format)): Path<(
+        String,
+        String,
+        String,
+        String,
+        String,
+    )>,
+    request: Request<Body>,
+    cache: ImageCache,
+) -> Result<Response, (StatusCode, String)> {
+    if !path_is_valid(&file_name) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid file name".to_string()));
+    }
+    let iiif_request = IiifRequest::parse(&region, &size, &rotation, &quality_format)
+        .map_err(iiif_error_response)?;
+
+    let source = tokio::fs::metadata(std::path::Path::new(IMAGES_DIR).join(&file_name))
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    let format = iiif_request.format.image_format();
+    let key = cache_key(
+        &file_name,
+        &source,
+        &format!("iiif/{}/{}/{}/{}", region, size, rotation, quality_format),
+        format,
+    );
+
+    if let Some(file) = cache.get(&key) {
+        return Ok(serve_file(file, request).await);
+    }
+
+    let bytes = tokio::task::spawn_blocking(move || {
+        let bytes = encode_iiif(&file_name, &iiif_request)?;
+        if let Err(e) = cache.insert(&key, &bytes) {
+            tracing::warn!("failed to cache {}: {}", key, e);
+        }
+        Ok::<_, (StatusCode, String)>(bytes)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))??;
+
+    Ok(([(header::CONTENT_TYPE, format.to_mime_type())], bytes).into_response())
+}
+
+/// Decodes the image with its EXIF orientation applied, then applies the IIIF request.
+fn encode_iiif(
+    file_name: &str,
+    iiif_request: &IiifRequest,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let orientation = image_metadata::fetch_image_metadata(file_name).orientation;
+    let image = decode_oriented(file_name, orientation)?;
+    let image = iiif_request.apply(image).map_err(iiif_error_response)?;
+
+    image_formats::encode(&image, iiif_request.format.image_format())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn iiif_error_response(e: IiifError) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, json!({ "error": e }).to_string())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IiifInfoResponse {
+    #[serde(rename = "@context")]
+    context: &'static str,
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    protocol: &'static str,
+    profile: &'static str,
+    width: u32,
+    height: u32,
+    max_width: u32,
+    max_height: u32,
+    extra_formats: [&'static str; 3],
+    extra_qualities: [&'static str; 3],
+    extra_features: [&'static str; 6],
+}
+
+/// Serves the IIIF image information of the image, as displayed with its EXIF orientation.
+async fn serve_iiif_info(
+    Path(file_name): Path<String>,
+    host: Option<TypedHeader<Host>>,
+) -> Result<Json<IiifInfoResponse>, (StatusCode, String)> {
+    if !path_is_valid(&file_name) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid file name".to_string()));
+    }
+
+    let id = match host {
+        Some(TypedHeader(host)) => format!("http://{}/iiif/{}", host, file_name),
+        None => format!("/iiif/{}", file_name),
+    };
+    let (width, height) = tokio::task::spawn_blocking(move || {
+        let dimensions = image_dimensions::fetch_image_dimensions(&file_name)
+            .map_err(|e| (StatusCode::NOT_FOUND, format!("{:?}", e)))?;
+        let orientation = image_metadata::fetch_image_metadata(&file_name).orientation;
+        Ok::<_, (StatusCode, String)>(image_orientation::oriented_dimensions(
+            dimensions,
+            orientation,
+        ))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))??;
+
+    Ok(Json(IiifInfoResponse {
+        context: "http://iiif.io/api/image/3/context.json",
+        id,
+        kind: "ImageService3",
+        protocol: "http://iiif.io/api/image",
+        profile: "level2",
+        width,
+        height,
+        max_width: iiif::MAX_DIMENSION,
+        max_height: iiif::MAX_DIMENSION,
+        extra_formats: ["png", "gif", "webp"],
+        extra_qualities: ["color", "gray", "bitonal"],
+        extra_features: [
+            "mirroring",
+            "regionByPct",
+            "regionSquare",
+            "rotationBy90s",
+            "sizeByPct",
+            "sizeUpscaling",
+        ],
+    }))
 }
@@ -0,0 +1,188 @@
+use std::{env, fs::File, io::BufReader, path::Path};
+
+use image::{
+    io::{Limits, Reader as ImageReader},
+    ImageFormat,
+};
+use serde::Serialize;
+
+use crate::api::{
+    image_dimensions::{self, FetchImageDimensionsError},
+    image_formats,
+};
+
+const DEFAULT_MAX_MEGAPIXELS: f64 = 100.0;
+const DEFAULT_MAX_BYTE_SIZE: u64 = 100 * 1024 * 1024; /* 100mb */
+const DEFAULT_ALLOWED_FORMATS: [ImageFormat; 6] = [
+    ImageFormat::Jpeg,
+    ImageFormat::Png,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+    ImageFormat::Bmp,
+    ImageFormat::Tiff,
+];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenImageError {
+    Unreadable(FetchImageDimensionsError),
+    #[serde(rename_all = "camelCase")]
+    TooManyPixels {
+        width: u32,
+        height: u32,
+        max_megapixels: f64,
+    },
+}
+
+/// Limits on the images accepted by uploads.
+#[derive(Clone, Debug)]
+pub struct IngestLimits {
+    /// Limits the decoded size of images, since a small file can decode into a huge image.
+    pub max_megapixels: f64,
+    pub max_byte_size: u64,
+    pub allowed_formats: Vec<ImageFormat>,
+}
+
+impl Default for IngestLimits {
+    fn default() -> Self {
+        Self {
+            max_megapixels: DEFAULT_MAX_MEGAPIXELS,
+            max_byte_size: DEFAULT_MAX_BYTE_SIZE,
+            allowed_formats: DEFAULT_ALLOWED_FORMATS.to_vec(),
+        }
+    }
+}
+
+impl IngestLimits {
+    /// Reads the limits from `UPLOAD_MAX_MEGAPIXELS`, `UPLOAD_MAX_BYTE_SIZE` and
+    /// `UPLOAD_ALLOWED_FORMATS`, a comma separated list of extensions like `jpg,png`.
+    /// Unset variables keep their defaults.
+    pub fn from_env() -> Self {
+        let mut limits = Self::default();
+        if let Ok(v) = env::var("UPLOAD_MAX_MEGAPIXELS") {
+            limits.max_megapixels = v.parse().expect("UPLOAD_MAX_MEGAPIXELS must be a number");
+        }
+        if let Ok(v) = env::var("UPLOAD_MAX_BYTE_SIZE") {
+            limits.max_byte_size = v
+                .parse()
+                .expect("UPLOAD_MAX_BYTE_SIZE must be a number of bytes");
+        }
+        if let Ok(v) = env::var("UPLOAD_ALLOWED_FORMATS") {
+            limits.allowed_formats =
+                parse_formats(&v).expect("UPLOAD_ALLOWED_FORMATS must list known image formats");
+        }
+        limits
+    }
+
+    pub fn allows(&self, format: ImageFormat) -> bool {
+        self.allowed_formats.contains(&format)
+    }
+
+    pub fn allowed_format_names(&self) -> Vec<String> {
+        self.allowed_formats
+            .iter()
+            .map(|&f| image_formats::format_name(f).to_string())
+            .collect()
+    }
+
+    pub fn exceeds_megapixels(&self, (width, height): (u32, u32)) -> bool {
+        width as f64 * height as f64 > self.max_megapixels * 1_000_000.0
+    }
+
+    /// Opens the image to be decoded, once its dimensions are read from its header and found
+    /// within `max_megapixels`. The decoder is held to those dimensions, so a file replaced since
+    /// they were read isn't decoded past them either.
+    pub fn open_within(&self, path: &Path) -> Result<ImageReader<BufReader<File>>, OpenImageError> {
+        let (width, height) = image_dimensions::fetch_image_dimensions_at(path)
+            .map_err(OpenImageError::Unreadable)?;
+        if self.exceeds_megapixels((width, height)) {
+            return Err(OpenImageError::TooManyPixels {
+                width,
+                height,
+                max_megapixels: self.max_megapixels,
+            });
+        }
+
+        let mut reader = ImageReader::open(path)
+            .and_then(|r| r.with_guessed_format())
+            .map_err(|e| {
+                OpenImageError::Unreadable(FetchImageDimensionsError::ErrorOpeningImage(
+                    e.to_string(),
+                ))
+            })?;
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(width);
+        limits.max_image_height = Some(height);
+        reader.limits(limits);
+        Ok(reader)
+    }
+}
+
+/// Parses a comma separated list of extensions. Returns `None` if one isn't an image format.
+fn parse_formats(formats: &str) -> Option<Vec<ImageFormat>> {
+    formats
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(ImageFormat::from_extension)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_formats_accepts_extensions() {
+        assert_eq!(
+            Some(vec![ImageFormat::Jpeg, ImageFormat::Png]),
+            parse_formats("jpeg, png,")
+        );
+    }
+
+    #[test]
+    fn parse_formats_rejects_unknown_format() {
+        assert_eq!(None, parse_formats("jpg,docx"));
+    }
+
+    #[test]
+    fn exceeds_megapixels_compares_pixel_count() {
+        // Arrange
+        let sut = IngestLimits {
+            max_megapixels: 1.0,
+            ..Default::default()
+        };
+
+        // Act & Assert
+        assert!(!sut.exceeds_megapixels((1000, 1000)));
+        assert!(sut.exceeds_megapixels((1000, 1001)));
+        assert!(sut.exceeds_megapixels((50_000, 50_000)));
+    }
+
+    #[test]
+    fn open_within_rejects_image_over_megapixels() {
+        // Arrange
+        let sut = IngestLimits {
+            max_megapixels: 0.0001,
+            ..Default::default()
+        };
+        let path = env::temp_dir().join(format!("limits-test-{:016x}.png", rand::random::<u64>()));
+        image::DynamicImage::new_rgb8(20, 10)
+            .save(&path)
+            .expect("image should be writable");
+
+        // Act
+        let res = sut.open_within(&path);
+
+        // Assert
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(
+            res,
+            Err(OpenImageError::TooManyPixels {
+                width: 20,
+                height: 10,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use image::{imageops::FilterType, DynamicImage};
+
+use crate::api::{image_orientation, ingest_limits::IngestLimits, IMAGES_DIR};
+
+/// Computes the dHash of the image file, with its EXIF `orientation` applied so rotated copies
+/// match. Images that can't be decoded within the `limits` don't have one.
+pub fn fetch_perceptual_hash(
+    file_name: &str,
+    orientation: Option<u16>,
+    limits: &IngestLimits,
+) -> Option<u64> {
+    fetch_perceptual_hash_at(&Path::new(IMAGES_DIR).join(file_name), orientation, limits)
+}
+
+/// Like [fetch_perceptual_hash], for an image outside of [IMAGES_DIR].
+pub fn fetch_perceptual_hash_at(
+    path: &Path,
+    orientation: Option<u16>,
+    limits: &IngestLimits,
+) -> Option<u64> {
+    let image = limits
+        .open_within(path)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|r| r.decode().map_err(|e| e.to_string()));
+
+    match image {
+        Ok(image) => Some(dhash(&image_orientation::orient_image(image, orientation))),
+        Err(e) => {
+            tracing::debug!("no perceptual hash for {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Computes a 64 bit difference hash: each bit tells whether a pixel of the image, shrunk to
+/// 9x8 and grayed, is brighter than its right neighbor.
+fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y).0[0] > small.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Groups the hashes whose Hamming distance is at most `max_distance`, directly or through other
+/// hashes in the group. Returns the indices of the hashes of every group with more than one hash.
+pub fn cluster(hashes: &[u64], max_distance: u32) -> Vec<Vec<usize>> {
+    // Union-find, where every index starts as its own root.
+    let mut parents: Vec<_> = (0..hashes.len()).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for i in 0..hashes.len() {
+        for j in (i + 1)..hashes.len() {
+            if hamming_distance(hashes[i], hashes[j]) <= max_distance {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); hashes.len()];
+    for i in 0..hashes.len() {
+        let r = root(&mut parents, i);
+        clusters[r].push(i);
+    }
+    clusters.retain(|c| c.len() > 1);
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    fn mk_gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let v = ((x * 255 / width) ^ (y * 255 / height)) as u8;
+            Rgb([v, v, v])
+        }))
+    }
+
+    #[test]
+    fn resized_copy_is_near() {
+        // Act
+        let original = dhash(&mk_gradient(400, 300));
+        let resized = dhash(&mk_gradient(200, 150));
+
+        // Assert
+        assert!(hamming_distance(original, resized) <= 4);
+    }
+
+    #[test]
+    fn cluster_joins_transitively() {
+        // Arrange
+        let hashes = [0b0000, 0b0001, 0b0011, 0xFFFF_0000];
+
+        // Act
+        let res = cluster(&hashes, 1);
+
+        // Assert
+        assert_eq!(vec![vec![0, 1, 2]], res);
+    }
+
+    #[test]
+    fn cluster_skips_unique_hashes() {
+        // Act
+        let res = cluster(&[0, u64::MAX], 10);
+
+        // Assert
+        assert!(res.is_empty());
+    }
+}
@@ -4,7 +4,8 @@ use axum::{
 };
 use hyper::{
     body::{self, Bytes, HttpBody},
-    Body, Request, StatusCode, Uri,
+    header::CONTENT_TYPE,
+    Body, HeaderMap, Request, StatusCode, Uri,
 };
 
 pub async fn print_request_response(
@@ -13,8 +14,14 @@ pub async fn print_request_response(
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let (parts, body) = req.into_parts();
     let uri = parts.uri.clone();
-    let bytes = buffer_and_print_request(&uri, body).await?;
-    let req = Request::from_parts(parts, Body::from(bytes));
+    // Binary bodies, like uploads, are passed through as they arrive, so an interrupted upload
+    // still reaches its handler.
+    let req = if has_text_body(&parts.headers) {
+        let bytes = buffer_and_print_request(&uri, body).await?;
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        Request::from_parts(parts, body)
+    };
 
     let res = next.run(req).await;
 
@@ -25,6 +32,15 @@ pub async fn print_request_response(
     Ok(res)
 }
 
+fn has_text_body(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    content_type.starts_with("text/")
+        || content_type.contains("json")
+        || content_type.starts_with("application/x-www-form-urlencoded")
+}
+
 async fn buffer_and_print_request<B>(uri: &Uri, body: B) -> Result<Bytes, (StatusCode, String)>
 where
     B: HttpBody<Data = Bytes>,
@@ -4,8 +4,13 @@ use serde_json::json;
 
 use crate::{persistence::PersistenceManager, state::screensaver_manager::ScreensaverManager};
 
+mod album;
+mod guest_link;
+mod idempotency;
 mod image;
 mod ping;
+mod tag;
+mod upload_batch;
 
 pub fn make_api_router(
     persistence_mngr: &PersistenceManager,
@@ -14,8 +19,15 @@ pub fn make_api_router(
     Router::new().nest(
         "/api",
         Router::new()
+            .merge(album::make_album_router(persistence_mngr))
+            .merge(guest_link::make_guest_link_router(persistence_mngr))
             .merge(image::make_image_router(persistence_mngr, screensaver_mngr))
-            .merge(ping::make_ping_router()),
+            .merge(ping::make_ping_router())
+            .merge(tag::make_tag_router(persistence_mngr))
+            .merge(upload_batch::make_upload_batch_router(
+                persistence_mngr,
+                screensaver_mngr,
+            )),
     )
 }
 
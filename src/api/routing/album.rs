@@ -0,0 +1,82 @@
+use axum::Router;
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::{
+    api::routing::ApiError,
+    domain::{actions::album::AlbumImagesError, models::Album},
+    persistence::PersistenceManager,
+};
+
+mod add_images;
+mod create;
+mod delete;
+mod images;
+mod list;
+mod remove_images;
+mod rename;
+
+pub fn make_album_router(persistence_mngr: &PersistenceManager) -> Router {
+    Router::new().nest(
+        "/album",
+        Router::new()
+            .merge(create::make_create_router(persistence_mngr.clone()))
+            .merge(rename::make_rename_router(persistence_mngr.clone()))
+            .merge(delete::make_delete_router(persistence_mngr.clone()))
+            .merge(list::make_list_router(persistence_mngr.clone()))
+            .merge(add_images::make_add_images_router(persistence_mngr.clone()))
+            .merge(remove_images::make_remove_images_router(
+                persistence_mngr.clone(),
+            ))
+            .merge(images::make_images_router(persistence_mngr.clone())),
+    )
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumResponse {
+    name: String,
+    image_count: u64,
+}
+
+impl From<Album> for AlbumResponse {
+    fn from(value: Album) -> Self {
+        Self {
+            name: value.name,
+            image_count: value.image_count,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AlbumImagesApiError {
+    AlbumNotFound,
+    ImagesNotFound(Vec<String>),
+    Persistence(String),
+}
+
+impl ApiError for AlbumImagesApiError {}
+
+impl From<AlbumImagesError> for (StatusCode, String) {
+    fn from(value: AlbumImagesError) -> Self {
+        match value {
+            AlbumImagesError::AlbumNotFound => (
+                StatusCode::NOT_FOUND,
+                AlbumImagesApiError::AlbumNotFound.to_json_string(),
+            ),
+            AlbumImagesError::ImagesNotFound(names) => (
+                StatusCode::NOT_FOUND,
+                AlbumImagesApiError::ImagesNotFound(names).to_json_string(),
+            ),
+            AlbumImagesError::Persistence(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                AlbumImagesApiError::Persistence(e).to_json_string(),
+            ),
+        }
+    }
+}
+
+fn name_is_valid(name: &str) -> bool {
+    !name.trim().is_empty()
+}
@@ -0,0 +1,25 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::Deserialize;
+
+use crate::domain::actions::album::AddAlbumImages;
+
+pub fn make_add_images_router(aai: impl 'static + Clone + Send + Sync + AddAlbumImages) -> Router {
+    Router::new().route("/add_images", post(|body| add_images(body, aai)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddImagesInput {
+    album_name: String,
+    file_names: Vec<String>,
+}
+
+async fn add_images(
+    Json(input): Json<AddImagesInput>,
+    aai: impl AddAlbumImages,
+) -> Result<(), (StatusCode, String)> {
+    aai.add_album_images(&input.album_name, &input.file_names)
+        .await
+        .map_err(|e| e.into())
+}
@@ -0,0 +1,64 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::routing::{album::name_is_valid, ApiError},
+    domain::actions::album::{CreateAlbum, FetchAlbum},
+};
+
+pub fn make_create_router(
+    album_mngr: impl 'static + Clone + Send + Sync + FetchAlbum + CreateAlbum,
+) -> Router {
+    Router::new().route("/create", post(|body| create_album(body, album_mngr)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateInput {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum CreateAlbumError {
+    InvalidName,
+    AlbumAlreadyExists,
+    Persistence(String),
+}
+
+impl ApiError for CreateAlbumError {}
+
+async fn create_album(
+    Json(input): Json<CreateInput>,
+    album_mngr: impl FetchAlbum + CreateAlbum,
+) -> Result<(), (StatusCode, String)> {
+    if !name_is_valid(&input.name) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            CreateAlbumError::InvalidName.to_json_string(),
+        ));
+    }
+
+    let existing_album = album_mngr.fetch_album(&input.name).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            CreateAlbumError::Persistence(e).to_json_string(),
+        )
+    })?;
+    if existing_album.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            CreateAlbumError::AlbumAlreadyExists.to_json_string(),
+        ));
+    }
+
+    album_mngr.create_album(&input.name).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            CreateAlbumError::Persistence(e).to_json_string(),
+        )
+    })?;
+
+    Ok(())
+}
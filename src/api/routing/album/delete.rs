@@ -0,0 +1,38 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{api::routing::ApiError, domain::actions::album::DeleteAlbum};
+
+pub fn make_delete_router(da: impl 'static + Clone + Send + Sync + DeleteAlbum) -> Router {
+    Router::new().route("/delete", post(|body| delete_album(body, da)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteInput {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum DeleteAlbumError {
+    Persistence(String),
+}
+
+impl ApiError for DeleteAlbumError {}
+
+// Deleting an album keeps its images, only the album and its memberships are removed.
+async fn delete_album(
+    Json(input): Json<DeleteInput>,
+    da: impl DeleteAlbum,
+) -> Result<(), (StatusCode, String)> {
+    da.delete_album(&input.name).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            DeleteAlbumError::Persistence(e).to_json_string(),
+        )
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use axum::{extract::Query, routing::get, Json, Router};
+use hyper::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    api::routing::image::{ImagesPageResponse, InputOrder},
+    domain::actions::{album::FetchAlbumImagesPage, tag::FetchImagesTags},
+};
+
+pub fn make_images_router(
+    album_mngr: impl 'static + Clone + Send + Sync + FetchAlbumImagesPage + FetchImagesTags,
+) -> Router {
+    Router::new().route("/images", get(|query| get_images(query, album_mngr)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumImagesPageInput {
+    album_name: String,
+    count: u64,
+    after: Option<i32>,
+    order: Option<InputOrder>,
+}
+
+async fn get_images(
+    Query(input): Query<AlbumImagesPageInput>,
+    album_mngr: impl FetchAlbumImagesPage + FetchImagesTags,
+) -> Result<Json<ImagesPageResponse>, (StatusCode, String)> {
+    let album_name = &input.album_name;
+    let page = album_mngr
+        .fetch_album_images_page(album_name, input.count, input.after, input.order.into())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Could not find album with name {}", album_name),
+            )
+        })?;
+
+    ImagesPageResponse::with_tags(page, album_mngr)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map(Json)
+}
@@ -0,0 +1,26 @@
+use axum::{routing::get, Json, Router};
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::{api::routing::album::AlbumResponse, domain::actions::album::FetchAlbums};
+
+pub fn make_list_router(fa: impl 'static + Clone + Send + Sync + FetchAlbums) -> Router {
+    Router::new().route("/list", get(|| list_albums(fa)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumsResponse {
+    albums: Vec<AlbumResponse>,
+}
+
+async fn list_albums(fa: impl FetchAlbums) -> Result<Json<AlbumsResponse>, (StatusCode, String)> {
+    fa.fetch_albums()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map(|v| {
+            Json(AlbumsResponse {
+                albums: v.into_iter().map(|v| v.into()).collect(),
+            })
+        })
+}
@@ -0,0 +1,27 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::Deserialize;
+
+use crate::domain::actions::album::RemoveAlbumImages;
+
+pub fn make_remove_images_router(
+    rai: impl 'static + Clone + Send + Sync + RemoveAlbumImages,
+) -> Router {
+    Router::new().route("/remove_images", post(|body| remove_images(body, rai)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoveImagesInput {
+    album_name: String,
+    file_names: Vec<String>,
+}
+
+async fn remove_images(
+    Json(input): Json<RemoveImagesInput>,
+    rai: impl RemoveAlbumImages,
+) -> Result<(), (StatusCode, String)> {
+    rai.remove_album_images(&input.album_name, &input.file_names)
+        .await
+        .map_err(|e| e.into())
+}
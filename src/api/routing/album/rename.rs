@@ -0,0 +1,80 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::routing::{album::name_is_valid, ApiError},
+    domain::actions::album::{FetchAlbum, RenameAlbum},
+};
+
+pub fn make_rename_router(
+    album_mngr: impl 'static + Clone + Send + Sync + FetchAlbum + RenameAlbum,
+) -> Router {
+    Router::new().route("/rename", post(|body| rename_album(body, album_mngr)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameInput {
+    old_name: String,
+    new_name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum RenameAlbumError {
+    InvalidName,
+    AlbumNotFound,
+    AlbumAlreadyExists,
+    Persistence(String),
+}
+
+impl ApiError for RenameAlbumError {}
+
+async fn rename_album(
+    Json(input): Json<RenameInput>,
+    album_mngr: impl FetchAlbum + RenameAlbum,
+) -> Result<(), (StatusCode, String)> {
+    if !name_is_valid(&input.new_name) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            RenameAlbumError::InvalidName.to_json_string(),
+        ));
+    }
+
+    let persistence_err = |e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            RenameAlbumError::Persistence(e).to_json_string(),
+        )
+    };
+    if album_mngr
+        .fetch_album(&input.old_name)
+        .await
+        .map_err(persistence_err)?
+        .is_none()
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            RenameAlbumError::AlbumNotFound.to_json_string(),
+        ));
+    }
+    if album_mngr
+        .fetch_album(&input.new_name)
+        .await
+        .map_err(persistence_err)?
+        .is_some()
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            RenameAlbumError::AlbumAlreadyExists.to_json_string(),
+        ));
+    }
+
+    album_mngr
+        .rename_album(&input.old_name, &input.new_name)
+        .await
+        .map_err(persistence_err)?;
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use axum::Router;
+use serde::Serialize;
+
+use crate::{domain::models::GuestLink, persistence::PersistenceManager};
+
+mod create;
+mod delete;
+mod list;
+
+pub fn make_guest_link_router(persistence_mngr: &PersistenceManager) -> Router {
+    Router::new().nest(
+        "/guest_link",
+        Router::new()
+            .merge(create::make_create_router(persistence_mngr.clone()))
+            .merge(delete::make_delete_router(persistence_mngr.clone()))
+            .merge(list::make_list_router(persistence_mngr.clone())),
+    )
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GuestLinkResponse {
+    token: String,
+    /// The path guests upload to.
+    upload_path: String,
+    label: Option<String>,
+    /// Formatted as `YYYY-MM-DDTHH:MM:SSZ`, in UTC.
+    created_at: String,
+    /// Formatted like `created_at`.
+    expires_at: String,
+    max_uploads: u32,
+    max_byte_size: u64,
+    upload_count: u32,
+    uploaded_byte_size: u64,
+}
+
+impl From<GuestLink> for GuestLinkResponse {
+    fn from(value: GuestLink) -> Self {
+        Self {
+            upload_path: format!("/api/image/guest/{}", value.token),
+            token: value.token,
+            label: value.label,
+            created_at: value.created_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            expires_at: value.expires_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            max_uploads: value.max_uploads,
+            max_byte_size: value.max_byte_size,
+            upload_count: value.upload_count,
+            uploaded_byte_size: value.uploaded_byte_size,
+        }
+    }
+}
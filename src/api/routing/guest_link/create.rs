@@ -0,0 +1,69 @@
+use axum::{routing::post, Json, Router};
+use chrono::{Duration, Utc};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::routing::{guest_link::GuestLinkResponse, ApiError},
+    domain::{actions::guest_link::CreateGuestLink, models::GuestLink},
+};
+
+const DEFAULT_EXPIRES_IN_HOURS: u32 = 24;
+const DEFAULT_MAX_UPLOADS: u32 = 100;
+const DEFAULT_MAX_BYTE_SIZE: u64 = 1024 * 1024 * 1024; /* 1gb */
+
+pub fn make_create_router(cgl: impl 'static + Clone + Send + Sync + CreateGuestLink) -> Router {
+    Router::new().route("/create", post(|body| create_guest_link(body, cgl)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateInput {
+    label: Option<String>,
+    expires_in_hours: Option<u32>,
+    max_uploads: Option<u32>,
+    max_byte_size: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum CreateGuestLinkError {
+    InvalidExpiry,
+    Persistence(String),
+}
+
+impl ApiError for CreateGuestLinkError {}
+
+// The token is the only thing guests need, so it's random and long enough not to be guessed.
+async fn create_guest_link(
+    Json(input): Json<CreateInput>,
+    cgl: impl CreateGuestLink,
+) -> Result<Json<GuestLinkResponse>, (StatusCode, String)> {
+    let expires_in_hours = input.expires_in_hours.unwrap_or(DEFAULT_EXPIRES_IN_HOURS);
+    if expires_in_hours == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            CreateGuestLinkError::InvalidExpiry.to_json_string(),
+        ));
+    }
+
+    let now = Utc::now().naive_utc();
+    let link = GuestLink {
+        token: format!("{:032x}", rand::random::<u128>()),
+        label: input.label,
+        created_at: now,
+        expires_at: now + Duration::hours(expires_in_hours as i64),
+        max_uploads: input.max_uploads.unwrap_or(DEFAULT_MAX_UPLOADS),
+        max_byte_size: input.max_byte_size.unwrap_or(DEFAULT_MAX_BYTE_SIZE),
+        upload_count: 0,
+        uploaded_byte_size: 0,
+    };
+    cgl.create_guest_link(&link).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            CreateGuestLinkError::Persistence(e).to_json_string(),
+        )
+    })?;
+
+    Ok(Json(link.into()))
+}
@@ -0,0 +1,38 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{api::routing::ApiError, domain::actions::guest_link::DeleteGuestLink};
+
+pub fn make_delete_router(dgl: impl 'static + Clone + Send + Sync + DeleteGuestLink) -> Router {
+    Router::new().route("/delete", post(|body| delete_guest_link(body, dgl)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteInput {
+    token: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum DeleteGuestLinkError {
+    Persistence(String),
+}
+
+impl ApiError for DeleteGuestLinkError {}
+
+// Deleting a link stops further uploads through it. Its uploads stay, pending ones included.
+async fn delete_guest_link(
+    Json(input): Json<DeleteInput>,
+    dgl: impl DeleteGuestLink,
+) -> Result<(), (StatusCode, String)> {
+    dgl.delete_guest_link(&input.token).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            DeleteGuestLinkError::Persistence(e).to_json_string(),
+        )
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use axum::{routing::get, Json, Router};
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::{
+    api::routing::guest_link::GuestLinkResponse, domain::actions::guest_link::FetchGuestLinks,
+};
+
+pub fn make_list_router(fgl: impl 'static + Clone + Send + Sync + FetchGuestLinks) -> Router {
+    Router::new().route("/list", get(|| list_guest_links(fgl)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GuestLinksResponse {
+    links: Vec<GuestLinkResponse>,
+}
+
+async fn list_guest_links(
+    fgl: impl FetchGuestLinks,
+) -> Result<Json<GuestLinksResponse>, (StatusCode, String)> {
+    fgl.fetch_guest_links()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map(|v| {
+            Json(GuestLinksResponse {
+                links: v.into_iter().map(|v| v.into()).collect(),
+            })
+        })
+}
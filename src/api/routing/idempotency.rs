@@ -0,0 +1,280 @@
+use std::{
+    collections::HashSet,
+    env,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use axum::{
+    body::boxed,
+    extract::{OriginalUri, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Utc};
+use hyper::{
+    body,
+    header::{HeaderName, HeaderValue, CONTENT_TYPE},
+    Body, HeaderMap, Request, StatusCode,
+};
+use serde::Serialize;
+
+use crate::{
+    api::routing::ApiError,
+    domain::{
+        actions::idempotency::{FetchIdempotentResponse, SaveIdempotentResponse},
+        models::IdempotentResponse,
+    },
+};
+
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on replayed responses, so clients can tell them apart from new ones.
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+const DEFAULT_WINDOW_SECS: i64 = 24 * 60 * 60; /* 24h */
+
+/// Remembers the responses of requests sent with an `Idempotency-Key` header for a window, so a
+/// retried request gets the original response instead of running again. Keys are scoped to the
+/// method and path of the request.
+#[derive(Clone)]
+pub struct IdempotencyKeys<S> {
+    store: S,
+    window: Duration,
+    in_flight: InFlightKeys,
+    /// The headers saved and replayed along with the content type and body of a response.
+    replayed_headers: &'static [&'static str],
+}
+
+impl<S> IdempotencyKeys<S> {
+    /// Reads the window from `IDEMPOTENCY_WINDOW_SECS`, 24 hours when it's unset.
+    pub fn from_env(store: S) -> Self {
+        let window_secs = match env::var("IDEMPOTENCY_WINDOW_SECS") {
+            Ok(v) => v
+                .parse()
+                .expect("IDEMPOTENCY_WINDOW_SECS must be a number of seconds"),
+            Err(_) => DEFAULT_WINDOW_SECS,
+        };
+        Self {
+            store,
+            window: Duration::seconds(window_secs),
+            in_flight: InFlightKeys::default(),
+            replayed_headers: &[],
+        }
+    }
+
+    /// Saves the given headers of responses, like the batch an upload was saved to, so they're
+    /// replayed too.
+    pub fn replaying_headers(mut self, headers: &'static [&'static str]) -> Self {
+        self.replayed_headers = headers;
+        self
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum IdempotencyError {
+    #[serde(rename_all = "camelCase")]
+    InvalidKey {
+        max_len: usize,
+    },
+    KeyInUse,
+    GeneralError(String),
+}
+
+impl ApiError for IdempotencyError {}
+
+/// Middleware that replays the saved response of a request with a known `Idempotency-Key`.
+/// Requests without the header are passed through. Server errors aren't saved, so requests that
+/// failed that way run again when they're retried.
+pub async fn replay_idempotent_response<S>(
+    State(keys): State<IdempotencyKeys<S>>,
+    req: Request<Body>,
+    next: Next<Body>,
+) -> Response
+where
+    S: FetchIdempotentResponse + SaveIdempotentResponse,
+{
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(req).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                IdempotencyError::InvalidKey {
+                    max_len: MAX_KEY_LEN,
+                }
+                .to_json_string(),
+            )
+                .into_response()
+        }
+    };
+    // Nested routers only see the rest of the path.
+    let path = match req.extensions().get::<OriginalUri>() {
+        Some(OriginalUri(uri)) => uri.path().to_string(),
+        None => req.uri().path().to_string(),
+    };
+    let scope = format!("{} {}", req.method(), path);
+
+    // A retry sent while the original request still runs can't be answered yet.
+    let Some(_held) = keys.in_flight.hold(&scope, &key) else {
+        return (
+            StatusCode::CONFLICT,
+            IdempotencyError::KeyInUse.to_json_string(),
+        )
+            .into_response();
+    };
+
+    let now = Utc::now().naive_utc();
+    match keys
+        .store
+        .fetch_idempotent_response(&scope, &key, now - keys.window)
+        .await
+    {
+        Ok(Some(saved)) => return replay(saved),
+        Ok(None) => {}
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                IdempotencyError::GeneralError(e).to_json_string(),
+            )
+                .into_response()
+        }
+    }
+
+    let res = next.run(req).await;
+    if res.status().is_server_error() {
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let bytes = match body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                IdempotencyError::GeneralError(e.to_string()).to_json_string(),
+            )
+                .into_response()
+        }
+    };
+    match std::str::from_utf8(&bytes) {
+        Ok(body) => {
+            let saved = IdempotentResponse {
+                status_code: parts.status.as_u16(),
+                content_type: parts
+                    .headers
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+                body: body.to_string(),
+                headers: replayed_headers(keys.replayed_headers, &parts.headers),
+            };
+            // The request already ran, so its response is returned even if it can't be saved.
+            if let Err(e) = keys
+                .store
+                .save_idempotent_response(&scope, &key, &saved, now - keys.window)
+                .await
+            {
+                tracing::warn!("failed to save the response for {}: {}", scope, e);
+            }
+        }
+        Err(_) => tracing::warn!("not saving the binary response for {}", scope),
+    }
+
+    Response::from_parts(parts, boxed(Body::from(bytes)))
+}
+
+/// The values of the `names` in the `headers`. Values that aren't text are left out.
+fn replayed_headers(names: &[&str], headers: &HeaderMap) -> Vec<(String, String)> {
+    names
+        .iter()
+        .flat_map(|&name| {
+            headers
+                .get_all(name)
+                .iter()
+                .filter_map(move |v| Some((name.to_string(), v.to_str().ok()?.to_string())))
+        })
+        .collect()
+}
+
+fn replay(saved: IdempotentResponse) -> Response {
+    let status = StatusCode::from_u16(saved.status_code).unwrap_or(StatusCode::OK);
+    let mut res = (status, saved.body).into_response();
+    let headers = res.headers_mut();
+    headers.remove(CONTENT_TYPE);
+    if let Some(content_type) = saved.content_type.and_then(|v| v.parse().ok()) {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    for (name, value) in saved.headers {
+        match (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            (Ok(name), Ok(value)) => {
+                headers.append(name, value);
+            }
+            _ => tracing::warn!("not replaying an invalid saved header"),
+        }
+    }
+    headers.insert(REPLAYED_HEADER, "true".parse().expect("valid header"));
+    res
+}
+
+/// The scoped keys of requests being handled.
+#[derive(Clone, Default)]
+struct InFlightKeys(Arc<Mutex<HashSet<(String, String)>>>);
+
+impl InFlightKeys {
+    /// Holds the scoped key until the returned guard is dropped.
+    /// Returns `None` if another request holds it.
+    fn hold(&self, scope: &str, key: &str) -> Option<InFlightKeyGuard> {
+        let scoped_key = (scope.to_string(), key.to_string());
+        if self.acquire_lock().insert(scoped_key.clone()) {
+            Some(InFlightKeyGuard {
+                keys: self.clone(),
+                scoped_key,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// In this case, we don't care if the mutex is poisoned, as the set is only modified by
+    /// single inserts and removes.
+    fn acquire_lock(&self) -> MutexGuard<'_, HashSet<(String, String)>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poison) => {
+                tracing::debug!("Accessing poisoned mutex");
+                poison.into_inner()
+            }
+        }
+    }
+}
+
+struct InFlightKeyGuard {
+    keys: InFlightKeys,
+    scoped_key: (String, String),
+}
+
+impl Drop for InFlightKeyGuard {
+    fn drop(&mut self) {
+        self.keys.acquire_lock().remove(&self.scoped_key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hold_rejects_held_key_until_released() {
+        let sut = InFlightKeys::default();
+
+        let held = sut.hold("POST /api/image/delete", "a");
+        assert!(held.is_some());
+        assert!(sut.hold("POST /api/image/delete", "a").is_none());
+        assert!(sut.hold("POST /api/image/rename", "a").is_some());
+
+        drop(held);
+        assert!(sut.hold("POST /api/image/delete", "a").is_some());
+    }
+}
@@ -1,28 +1,64 @@
-use axum::Router;
-use serde::Serialize;
+use axum::{middleware, Router};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    domain::models::Image, persistence::PersistenceManager,
+    api::routing::idempotency::{self, IdempotencyKeys},
+    domain::{
+        actions::{image::PaginationOrder, tag::FetchImagesTags},
+        models::{Image, ImageMetadata, ImagesPage},
+    },
+    persistence::PersistenceManager,
     state::screensaver_manager::ScreensaverManager,
 };
 
 mod current;
 mod delete;
 mod get;
+mod guest;
+mod moderation;
+mod near_duplicates;
 mod paginated;
 mod rename;
 mod resolve;
+mod tus;
 mod update_canon;
 mod upload;
+mod upload_details;
 
 pub fn make_image_router(
     persistence_mngr: &PersistenceManager,
     screensaver_mngr: &ScreensaverManager,
 ) -> Router {
+    // Retried uploads, renames and deletes get the response of the original request.
+    let idempotent_router = Router::new()
+        .merge(upload::make_upload_router(
+            persistence_mngr.clone(),
+            screensaver_mngr.clone(),
+        ))
+        .merge(rename::make_rename_router(
+            persistence_mngr.clone(),
+            screensaver_mngr.clone(),
+        ))
+        .merge(delete::make_delete_router(
+            persistence_mngr.clone(),
+            screensaver_mngr.clone(),
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            IdempotencyKeys::from_env(persistence_mngr.clone())
+                .replaying_headers(&[upload::UPLOAD_BATCH_HEADER]),
+            idempotency::replay_idempotent_response,
+        ));
+
     Router::new().nest(
         "/image",
         Router::new()
-            .merge(upload::make_upload_router(
+            .merge(idempotent_router)
+            .merge(tus::make_tus_router(
+                persistence_mngr.clone(),
+                screensaver_mngr.clone(),
+            ))
+            .merge(guest::make_guest_router(persistence_mngr.clone()))
+            .merge(moderation::make_moderation_router(
                 persistence_mngr.clone(),
                 screensaver_mngr.clone(),
             ))
@@ -32,25 +68,82 @@ pub fn make_image_router(
                 screensaver_mngr.clone(),
             ))
             .merge(paginated::make_paginated_router(persistence_mngr.clone()))
-            .merge(rename::make_rename_router(
+            .merge(near_duplicates::make_near_duplicates_router(
                 persistence_mngr.clone(),
-                screensaver_mngr.clone(),
             ))
             .merge(current::make_current_router(screensaver_mngr.clone()))
             .merge(resolve::make_resolve_router(screensaver_mngr.clone()))
-            .merge(delete::make_delete_router(
-                persistence_mngr.clone(),
-                screensaver_mngr.clone(),
-            )),
+            .layer(middleware::from_fn(upload::reject_reserved_file_names)),
     )
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ImageResponse {
+pub struct ImageResponse {
     file_name: String,
     width: u32,
     height: u32,
+    /// `None` when the tags weren't fetched, like for the screensaver.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    /// `None` when the metadata wasn't fetched, like for pages of images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<ImageMetadataResponse>,
+}
+
+impl ImageResponse {
+    fn with_tags(self, tags: Vec<String>) -> Self {
+        Self {
+            tags: Some(tags),
+            ..self
+        }
+    }
+
+    fn with_metadata(self, metadata: ImageMetadata) -> Self {
+        Self {
+            metadata: Some(metadata.into()),
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadataResponse {
+    /// Formatted as `YYYY-MM-DDTHH:MM:SS`, in the camera's local time.
+    taken_at: Option<String>,
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    lens_model: Option<String>,
+    /// In seconds.
+    exposure_time: Option<f64>,
+    f_number: Option<f64>,
+    iso: Option<u32>,
+    /// In millimeters.
+    focal_length: Option<f64>,
+    orientation: Option<u16>,
+    gps_latitude: Option<f64>,
+    gps_longitude: Option<f64>,
+}
+
+impl From<ImageMetadata> for ImageMetadataResponse {
+    fn from(value: ImageMetadata) -> Self {
+        Self {
+            taken_at: value
+                .taken_at
+                .map(|v| v.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            camera_make: value.camera_make,
+            camera_model: value.camera_model,
+            lens_model: value.lens_model,
+            exposure_time: value.exposure_time,
+            f_number: value.f_number,
+            iso: value.iso,
+            focal_length: value.focal_length,
+            orientation: value.orientation,
+            gps_latitude: value.gps_latitude,
+            gps_longitude: value.gps_longitude,
+        }
+    }
 }
 
 impl From<Image> for ImageResponse {
@@ -59,6 +152,52 @@ impl From<Image> for ImageResponse {
             file_name: value.file_name,
             width: value.width,
             height: value.height,
+            tags: None,
+            metadata: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputOrder {
+    NewToOld,
+    OldToNew,
+}
+
+impl From<Option<InputOrder>> for PaginationOrder {
+    fn from(value: Option<InputOrder>) -> Self {
+        match value {
+            Some(InputOrder::NewToOld) => PaginationOrder::NewToOld,
+            Some(InputOrder::OldToNew) => PaginationOrder::OldToNew,
+            None => PaginationOrder::OldToNew,
         }
     }
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagesPageResponse {
+    images: Vec<ImageResponse>,
+    cursor: Option<i32>,
+}
+
+impl ImagesPageResponse {
+    /// Creates an [ImagesPageResponse] including the tags of every image.
+    pub async fn with_tags(page: ImagesPage, fit: impl FetchImagesTags) -> Result<Self, String> {
+        let file_names: Vec<_> = page.images.iter().map(|i| i.file_name.clone()).collect();
+        let mut tags = fit.fetch_images_tags(&file_names).await?;
+
+        Ok(Self {
+            cursor: page.cursor,
+            images: page
+                .images
+                .into_iter()
+                .map(|v| {
+                    let image_tags = tags.remove(&v.file_name).unwrap_or_default();
+                    ImageResponse::from(v).with_tags(image_tags)
+                })
+                .collect(),
+        })
+    }
+}
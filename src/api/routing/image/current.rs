@@ -1,12 +1,21 @@
-use axum::{routing::get, Json, Router};
-use serde::Serialize;
+use axum::{extract::Query, routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
 
-use crate::{api::routing::image::ImageResponse, domain::screensaver::Screensaver};
+use crate::{api::routing::image::ImageResponse, domain::screensaver::DeviceScreensaver};
 
 pub fn make_current_router(
-    screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+    screensaver: impl 'static + Clone + Send + Sync + DeviceScreensaver,
 ) -> Router {
-    Router::new().route("/current", get(|| async { current(screensaver) }))
+    Router::new().route(
+        "/current",
+        get(|query| async { current(query, screensaver) }),
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurrentInput {
+    device_id: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -15,8 +24,13 @@ struct CurrentResponse {
     image: Option<ImageResponse>,
 }
 
-fn current(screensaver: impl Screensaver) -> Json<CurrentResponse> {
+fn current(
+    Query(input): Query<CurrentInput>,
+    screensaver: impl DeviceScreensaver,
+) -> Json<CurrentResponse> {
     Json(CurrentResponse {
-        image: screensaver.current().map(|i| i.into()),
+        image: screensaver
+            .current_for_device(input.device_id.as_deref())
+            .map(|i| i.into()),
     })
 }
@@ -1,10 +1,12 @@
+use std::io;
+
 use axum::{routing::post, Json, Router};
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 
 use crate::{
-    api::{routing::ApiError, IMAGES_DIR},
+    api::{image_derivatives, routing::ApiError, IMAGES_DIR, PENDING_DIR},
     domain::{actions::image::DeleteImage, screensaver::Screensaver},
 };
 
@@ -26,7 +28,6 @@ struct DeleteInput {
 enum DeleteImageError {
     Fs(String),
     Persistence(String),
-    FailedToDeleteInQueue,
 }
 
 impl ApiError for DeleteImageError {}
@@ -43,6 +44,15 @@ async fn delete_image(
         )
     })?;
 
+    delete_saved(&input, di, &mut screensaver).await
+}
+
+/// Deletes the saved image, then removes it from the screensaver.
+async fn delete_saved(
+    input: &DeleteInput,
+    di: impl DeleteImage,
+    screensaver: &mut impl Screensaver,
+) -> Result<(), (StatusCode, String)> {
     di.delete_image(&input.file_name).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -50,18 +60,76 @@ async fn delete_image(
         )
     })?;
 
-    screensaver.delete_image(&input.file_name).map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            DeleteImageError::FailedToDeleteInQueue.to_json_string(),
-        )
-    })?;
+    // Images excluded from the screensaver aren't in it.
+    let _ = screensaver.delete_image(&input.file_name);
 
     Ok(())
 }
 
 async fn delete_fs(input: &DeleteInput) -> Result<(), String> {
-    fs::remove_file(format!("{}/{}", IMAGES_DIR, input.file_name))
-        .await
+    // Images pending approval are in their own directory.
+    let remove_in = |dir| fs::remove_file(format!("{}/{}", dir, input.file_name));
+    match remove_in(IMAGES_DIR).await {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => remove_in(PENDING_DIR).await,
+        res => res,
+    }
+    .map_err(|e| e.to_string())?;
+
+    // The image is already gone, so leftover derivatives are only logged.
+    // They're removed by the next canon update.
+    let file_name = input.file_name.clone();
+    let res =
+        tokio::task::spawn_blocking(move || image_derivatives::delete_derivatives(&file_name))
+            .await;
+    if let Err(e) = res
         .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()))
+    {
+        tracing::warn!("failed to delete derivatives of {}: {}", input.file_name, e);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{domain::models::Image, state::screensaver_manager::ScreensaverManager};
+
+    struct SavedImages;
+
+    #[async_trait]
+    impl DeleteImage for SavedImages {
+        async fn delete_image(&self, _: &str) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_saved_accepts_image_excluded_from_screensaver() {
+        // Arrange
+        let img = Image {
+            file_name: "shown.png".to_string(),
+            width: 1,
+            height: 1,
+        };
+        let mut screensaver = ScreensaverManager::restore(
+            Vec::new(),
+            HashMap::from([(img.file_name.clone(), img.clone())]),
+        );
+        let input = DeleteInput {
+            file_name: "excluded.png".to_string(),
+        };
+
+        // Act
+        let res = delete_saved(&input, SavedImages, &mut screensaver).await;
+
+        // Assert
+        assert!(res.is_ok());
+        assert_eq!(Some(img), screensaver.current());
+    }
 }
@@ -2,10 +2,24 @@ use axum::{extract::Query, routing::get, Json, Router};
 use hyper::StatusCode;
 use serde::Deserialize;
 
-use crate::{api::routing::image::ImageResponse, domain::actions::image::FetchImage};
+use crate::{
+    api::routing::image::ImageResponse,
+    domain::actions::{
+        image::{FetchApprovedImage, FetchImageMetadata},
+        tag::FetchImagesTags,
+    },
+};
 
-pub fn make_get_router(fi: impl 'static + Clone + Send + Sync + FetchImage) -> Router {
-    Router::new().route("/get", get(|query| get_image(query, fi)))
+pub fn make_get_router(
+    image_mngr: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + FetchApprovedImage
+        + FetchImageMetadata
+        + FetchImagesTags,
+) -> Router {
+    Router::new().route("/get", get(|query| get_image(query, image_mngr)))
 }
 
 #[derive(Deserialize)]
@@ -16,11 +30,11 @@ struct FindImage {
 
 async fn get_image(
     Query(find_image): Query<FindImage>,
-    fi: impl FetchImage,
+    image_mngr: impl FetchApprovedImage + FetchImageMetadata + FetchImagesTags,
 ) -> Result<Json<ImageResponse>, (StatusCode, String)> {
     let file_name = &find_image.file_name;
-    let image = fi
-        .fetch_image(file_name)
+    let image = image_mngr
+        .fetch_approved_image(file_name)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
         .ok_or_else(|| {
@@ -30,5 +44,22 @@ async fn get_image(
             )
         })?;
 
-    Ok(Json(image.into()))
+    let tags = image_mngr
+        .fetch_images_tags(std::slice::from_ref(file_name))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .remove(file_name)
+        .unwrap_or_default();
+
+    let metadata = image_mngr
+        .fetch_image_metadata(file_name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .unwrap_or_default();
+
+    Ok(Json(
+        ImageResponse::from(image)
+            .with_tags(tags)
+            .with_metadata(metadata),
+    ))
 }
@@ -0,0 +1,220 @@
+use axum::{
+    extract::{self, multipart::Field, DefaultBodyLimit, Multipart},
+    routing::post,
+    Json, Router,
+};
+use chrono::Utc;
+use hyper::{HeaderMap, StatusCode};
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::{
+    api::{ingest_limits::IngestLimits, routing::ApiError},
+    domain::{
+        actions::{
+            guest_link::{FetchGuestLink, ReserveGuestUpload, ReserveGuestUploadError},
+            image::{DeleteImage, FetchImage, FetchImageByContentHash, SaveImage},
+        },
+        models::{GuestLink, Image, UploadDetails},
+    },
+};
+
+use super::upload::{
+    self, FileFieldValidationError, UploadImageError, UploadOutcome, UploadResult,
+};
+
+/// Lets guests upload through a link, without access to the rest of the API. Their uploads wait
+/// for approval, so they aren't served, listed or in the screensaver until they're approved.
+pub fn make_guest_router(
+    image_mngr: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + DeleteImage
+        + FetchGuestLink
+        + FetchImage
+        + FetchImageByContentHash
+        + ReserveGuestUpload
+        + SaveImage,
+) -> Router {
+    let limits = IngestLimits::from_env();
+    let body_limit = upload::request_body_limit(&limits);
+
+    Router::new()
+        .route(
+            "/guest/:token",
+            post(|token, headers, body| guest_upload(token, headers, body, image_mngr, limits)),
+        )
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(body_limit))
+}
+
+// Handler that accepts a multipart form upload like `/upload`, with an outcome per file. Parts
+// without a file are ignored, since guests can't set the details of their uploads.
+async fn guest_upload(
+    extract::Path(token): extract::Path<String>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+    image_mngr: impl DeleteImage
+        + FetchGuestLink
+        + FetchImage
+        + FetchImageByContentHash
+        + ReserveGuestUpload
+        + SaveImage,
+    limits: IngestLimits,
+) -> Result<([(&'static str, String); 1], Json<Vec<UploadResult>>), (StatusCode, String)> {
+    fetch_usable_link(&token, &image_mngr)
+        .await
+        .map_err(|(s, e)| (s, e.to_json_string()))?;
+    let upload_batch = upload::upload_batch_from(&headers)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_json_string()))?;
+    let details = UploadDetails {
+        upload_batch: Some(upload_batch.clone()),
+        guest_link: Some(token.clone()),
+        ..Default::default()
+    };
+
+    let mut results = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                // The rest of the body can't be read once a part is malformed.
+                results.push(UploadResult {
+                    file_name: None,
+                    outcome: UploadOutcome::Invalid(UploadImageError::FileFieldErr(
+                        FileFieldValidationError::FieldErr(e.to_string()),
+                    )),
+                });
+                break;
+            }
+        };
+        let Some(file_name) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+
+        let outcome = match upload_guest_file(field, &token, &image_mngr, &details, &limits).await {
+            Ok(_) => UploadOutcome::Ok,
+            Err(e) => UploadOutcome::from_error(e),
+        };
+        results.push(UploadResult {
+            file_name: Some(file_name),
+            outcome,
+        });
+    }
+
+    if results.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            UploadImageError::FileFieldErr(FileFieldValidationError::MissingField).to_json_string(),
+        ));
+    }
+
+    Ok(([(upload::UPLOAD_BATCH_HEADER, upload_batch)], Json(results)))
+}
+
+/// Uploads the file of a multipart part through the link, counting it against the limits of the
+/// link. Returns the saved image.
+async fn upload_guest_file(
+    field: Field<'_>,
+    token: &str,
+    image_mngr: &(impl DeleteImage
+          + FetchGuestLink
+          + FetchImage
+          + FetchImageByContentHash
+          + ReserveGuestUpload
+          + SaveImage),
+    details: &UploadDetails,
+    limits: &IngestLimits,
+) -> Result<Image, (StatusCode, UploadImageError)> {
+    let file_name = upload::validate_field(&field)
+        .map_err(|e| (StatusCode::BAD_REQUEST, UploadImageError::FileFieldErr(e)))?;
+    upload::reject_existing_file_name(&file_name, image_mngr).await?;
+
+    // The link is checked again, since earlier files may have used it up.
+    let link = fetch_usable_link(token, image_mngr).await?;
+    let remaining_byte_size = link.max_byte_size.saturating_sub(link.uploaded_byte_size);
+    if link.upload_count >= link.max_uploads || remaining_byte_size == 0 {
+        return Err(limit_reached(&link));
+    }
+
+    // A file over what's left of the link uses it up, rather than being too large.
+    let link_is_binding = remaining_byte_size < limits.max_byte_size;
+    let (staged_file, content_hash, byte_size) = upload::stage_upload(
+        &file_name,
+        limits.max_byte_size.min(remaining_byte_size),
+        field,
+    )
+    .await
+    .map_err(|e| match e {
+        (_, UploadImageError::FileTooLarge { .. }) if link_is_binding => limit_reached(&link),
+        e => e,
+    })?;
+
+    match image_mngr.reserve_guest_upload(token, byte_size).await {
+        Ok(()) => {}
+        Err(ReserveGuestUploadError::LinkNotFound) => {
+            return Err((StatusCode::NOT_FOUND, UploadImageError::GuestLinkNotFound))
+        }
+        Err(ReserveGuestUploadError::LinkExpired) => {
+            return Err((StatusCode::GONE, UploadImageError::GuestLinkExpired))
+        }
+        Err(ReserveGuestUploadError::LimitReached) => return Err(limit_reached(&link)),
+        Err(ReserveGuestUploadError::Persistence(e)) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                UploadImageError::GeneralError(e),
+            ))
+        }
+    }
+
+    let res = upload::ingest_staged(
+        file_name,
+        staged_file,
+        content_hash,
+        byte_size,
+        image_mngr,
+        details,
+        limits,
+    )
+    .await;
+    if res.is_err() {
+        // Failed uploads don't count against the link.
+        if let Err(e) = image_mngr.release_guest_upload(token, byte_size).await {
+            tracing::warn!("failed to release a guest upload of link {}: {}", token, e);
+        }
+    }
+    res
+}
+
+/// Fetches the link, unless it doesn't exist or it expired.
+async fn fetch_usable_link(
+    token: &str,
+    fgl: &impl FetchGuestLink,
+) -> Result<GuestLink, (StatusCode, UploadImageError)> {
+    let link = fgl
+        .fetch_guest_link(token)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                UploadImageError::GeneralError(e),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, UploadImageError::GuestLinkNotFound))?;
+    if link.expires_at <= Utc::now().naive_utc() {
+        return Err((StatusCode::GONE, UploadImageError::GuestLinkExpired));
+    }
+
+    Ok(link)
+}
+
+fn limit_reached(link: &GuestLink) -> (StatusCode, UploadImageError) {
+    (
+        StatusCode::FORBIDDEN,
+        UploadImageError::GuestLinkLimitReached {
+            max_uploads: link.max_uploads,
+            max_byte_size: link.max_byte_size,
+        },
+    )
+}
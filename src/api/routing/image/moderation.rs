@@ -0,0 +1,275 @@
+use std::{collections::HashSet, fs, io, path::Path};
+
+use axum::{
+    body::{boxed, Body},
+    extract,
+    http::Request,
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+use crate::{
+    api::{delete_image_files, path_is_valid, routing::ApiError, IMAGES_DIR, PENDING_DIR},
+    domain::{
+        actions::image::{
+            ApproveImages, DeleteImage, FetchPendingImages, FetchScreensaverExclusions,
+        },
+        models::PendingImage,
+        screensaver::Screensaver,
+    },
+};
+
+/// Approves or rejects the images uploaded by guests.
+pub fn make_moderation_router(
+    image_mngr: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + ApproveImages
+        + DeleteImage
+        + FetchPendingImages
+        + FetchScreensaverExclusions,
+    screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+) -> Router {
+    Router::new()
+        .route(
+            "/pending",
+            get({
+                let image_mngr = image_mngr.clone();
+                || list_pending_images(image_mngr)
+            }),
+        )
+        .route("/pending/:file_name", get(serve_pending_image))
+        .route(
+            "/approve",
+            post({
+                let (image_mngr, screensaver) = (image_mngr.clone(), screensaver.clone());
+                |body| approve_images(body, image_mngr, screensaver)
+            }),
+        )
+        .route("/reject", post(|body| reject_images(body, image_mngr)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ModerationError {
+    /// The files that couldn't be moved or removed. The rest of the images were approved or
+    /// rejected.
+    Fs(Vec<String>),
+    Persistence(String),
+    Blocking(String),
+}
+
+impl ApiError for ModerationError {}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingImagesResponse {
+    images: Vec<PendingImageResponse>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingImageResponse {
+    file_name: String,
+    width: u32,
+    height: u32,
+    guest_link_label: Option<String>,
+}
+
+impl From<PendingImage> for PendingImageResponse {
+    fn from(value: PendingImage) -> Self {
+        Self {
+            file_name: value.image.file_name,
+            width: value.image.width,
+            height: value.image.height,
+            guest_link_label: value.guest_link_label,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModerationInput {
+    file_names: Vec<String>,
+}
+
+/// Lists the file names among the input that were approved or rejected. The rest weren't
+/// pending approval.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModerationResponse {
+    file_names: Vec<String>,
+}
+
+async fn list_pending_images(
+    fpi: impl FetchPendingImages,
+) -> Result<Json<PendingImagesResponse>, (StatusCode, String)> {
+    let images = fpi
+        .fetch_pending_images()
+        .await
+        .map_err(persistence_error)?;
+
+    Ok(Json(PendingImagesResponse {
+        images: images.into_iter().map(|v| v.into()).collect(),
+    }))
+}
+
+/// Serves an image pending approval, since it isn't served with the rest until it's approved.
+async fn serve_pending_image(
+    extract::Path(file_name): extract::Path<String>,
+    request: Request<Body>,
+) -> Result<Response, (StatusCode, String)> {
+    if !path_is_valid(&file_name) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid file name".to_string()));
+    }
+
+    match ServeFile::new(Path::new(PENDING_DIR).join(file_name))
+        .oneshot(request)
+        .await
+    {
+        Ok(res) => Ok(res.map(boxed)),
+        Err(e) => match e {},
+    }
+}
+
+// Approved images are moved from the pending directory into the images directory first, then
+// they join the screensaver, unless they were uploaded excluded from it.
+async fn approve_images(
+    Json(input): Json<ModerationInput>,
+    image_mngr: impl ApproveImages + FetchPendingImages + FetchScreensaverExclusions,
+    mut screensaver: impl Screensaver,
+) -> Result<Json<ModerationResponse>, (StatusCode, String)> {
+    let pending = fetch_requested_pending(input.file_names, &image_mngr).await?;
+    let (moved, failed) =
+        run_blocking(move || move_image_files(pending, PENDING_DIR, IMAGES_DIR)).await?;
+
+    let approved = match image_mngr.approve_images(&moved).await {
+        Ok(approved) => approved,
+        Err(e) => {
+            // Unapproved images are moved back, so they aren't served.
+            let (_, failed) =
+                run_blocking(move || move_image_files(moved, IMAGES_DIR, PENDING_DIR)).await?;
+            if !failed.is_empty() {
+                tracing::error!("failed to move unapproved images back: {:?}", failed);
+            }
+            return Err(persistence_error(e));
+        }
+    };
+    let exclusions = image_mngr
+        .fetch_screensaver_exclusions()
+        .await
+        .map_err(persistence_error)?;
+
+    let file_names = approved.iter().map(|i| i.file_name.clone()).collect();
+    for image in approved {
+        if exclusions.contains(&image.file_name) {
+            continue;
+        }
+        let file_name = image.file_name.clone();
+        if screensaver.insert(image).is_err() {
+            tracing::warn!("approved image {} already in the screensaver", file_name);
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ModerationError::Fs(failed).to_json_string(),
+        ));
+    }
+
+    Ok(Json(ModerationResponse { file_names }))
+}
+
+// Rejected images are deleted, files first like when deleting an upload batch.
+async fn reject_images(
+    Json(input): Json<ModerationInput>,
+    image_mngr: impl DeleteImage + FetchPendingImages,
+) -> Result<Json<ModerationResponse>, (StatusCode, String)> {
+    let pending = fetch_requested_pending(input.file_names, &image_mngr).await?;
+
+    let (deleted, failed) = run_blocking(move || delete_image_files(pending)).await?;
+
+    for file_name in &deleted {
+        image_mngr
+            .delete_image(file_name)
+            .await
+            .map_err(persistence_error)?;
+    }
+
+    if !failed.is_empty() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ModerationError::Fs(failed).to_json_string(),
+        ));
+    }
+
+    Ok(Json(ModerationResponse {
+        file_names: deleted,
+    }))
+}
+
+/// Returns the requested file names of images pending approval.
+async fn fetch_requested_pending(
+    file_names: Vec<String>,
+    fpi: &impl FetchPendingImages,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let requested: HashSet<_> = file_names.into_iter().collect();
+
+    Ok(fpi
+        .fetch_pending_images()
+        .await
+        .map_err(persistence_error)?
+        .into_iter()
+        .map(|p| p.image.file_name)
+        .filter(|n| requested.contains(n))
+        .collect())
+}
+
+/// Moves the images from the directory `from` into `to`. Returns the file names of the moved
+/// images and of those that couldn't be moved. Images that are already in `to` count as moved.
+fn move_image_files(file_names: Vec<String>, from: &str, to: &str) -> (Vec<String>, Vec<String>) {
+    if let Err(e) = fs::create_dir_all(to) {
+        tracing::warn!("failed to create {}: {}", to, e);
+        return (Vec::new(), file_names);
+    }
+
+    let mut moved = Vec::new();
+    let mut failed = Vec::new();
+    for file_name in file_names {
+        let to_path = Path::new(to).join(&file_name);
+        match fs::rename(Path::new(from).join(&file_name), &to_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound || !to_path.exists() => {
+                tracing::warn!("failed to move {} into {}: {}", file_name, to, e);
+                failed.push(file_name);
+            }
+            _ => moved.push(file_name),
+        }
+    }
+
+    (moved, failed)
+}
+
+async fn run_blocking<T: 'static + Send>(
+    f: impl 'static + Send + FnOnce() -> T,
+) -> Result<T, (StatusCode, String)> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ModerationError::Blocking(e.to_string()).to_json_string(),
+        )
+    })
+}
+
+fn persistence_error(e: String) -> (StatusCode, String) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ModerationError::Persistence(e).to_json_string(),
+    )
+}
@@ -0,0 +1,94 @@
+use axum::{extract::Query, routing::get, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{
+        perceptual_hash,
+        routing::{image::ImageResponse, ApiError},
+    },
+    domain::actions::image::FetchPerceptualHashes,
+};
+
+/// The Hamming distance used when none is given. Resized and re-compressed copies of an image
+/// rarely differ by more than this.
+const DEFAULT_MAX_DISTANCE: u32 = 6;
+/// Perceptual hashes are 64 bits, so larger distances would match everything.
+const MAX_MAX_DISTANCE: u32 = 32;
+
+pub fn make_near_duplicates_router(
+    fph: impl 'static + Clone + Send + Sync + FetchPerceptualHashes,
+) -> Router {
+    Router::new().route(
+        "/near_duplicates",
+        get(|query| get_near_duplicates(query, fph)),
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NearDuplicatesInput {
+    /// The largest Hamming distance between the perceptual hashes of near-identical images.
+    max_distance: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum NearDuplicatesError {
+    MaxDistanceTooLarge { max: u32 },
+    Persistence(String),
+}
+
+impl ApiError for NearDuplicatesError {}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NearDuplicatesResponse {
+    /// Largest clusters first, with the images of each ordered by file name.
+    clusters: Vec<Vec<ImageResponse>>,
+}
+
+async fn get_near_duplicates(
+    Query(input): Query<NearDuplicatesInput>,
+    fph: impl FetchPerceptualHashes,
+) -> Result<Json<NearDuplicatesResponse>, (StatusCode, String)> {
+    let max_distance = input.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+    if max_distance > MAX_MAX_DISTANCE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            NearDuplicatesError::MaxDistanceTooLarge {
+                max: MAX_MAX_DISTANCE,
+            }
+            .to_json_string(),
+        ));
+    }
+
+    let images = fph.fetch_perceptual_hashes().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            NearDuplicatesError::Persistence(e).to_json_string(),
+        )
+    })?;
+    let hashes: Vec<_> = images.iter().map(|(_, hash)| *hash).collect();
+    let clusters =
+        tokio::task::spawn_blocking(move || perceptual_hash::cluster(&hashes, max_distance))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut clusters: Vec<Vec<_>> = clusters
+        .into_iter()
+        .map(|cluster| {
+            let mut cluster: Vec<_> = cluster.into_iter().map(|i| images[i].0.clone()).collect();
+            cluster.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+            cluster
+        })
+        .collect();
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+
+    Ok(Json(NearDuplicatesResponse {
+        clusters: clusters
+            .into_iter()
+            .map(|cluster| cluster.into_iter().map(ImageResponse::from).collect())
+            .collect(),
+    }))
+}
@@ -1,14 +1,22 @@
 use axum::{extract::Query, routing::get, Json, Router};
 use hyper::StatusCode;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
 use crate::{
-    api::routing::image::ImageResponse,
-    domain::actions::image::{FetchImagesPage, PaginationOrder},
+    api::routing::{
+        image::{ImagesPageResponse, InputOrder},
+        tag::split_tag_names,
+    },
+    domain::{
+        actions::{image::FetchImagesPage, tag::FetchImagesTags},
+        models::TagFilter,
+    },
 };
 
-pub fn make_paginated_router(fip: impl 'static + Clone + Send + Sync + FetchImagesPage) -> Router {
-    Router::new().route("/paginated", get(|query| get_images(query, fip)))
+pub fn make_paginated_router(
+    image_mngr: impl 'static + Clone + Send + Sync + FetchImagesPage + FetchImagesTags,
+) -> Router {
+    Router::new().route("/paginated", get(|query| get_images(query, image_mngr)))
 }
 
 #[derive(Deserialize)]
@@ -17,41 +25,35 @@ struct ImagesPageInput {
     count: u64,
     after: Option<i32>,
     order: Option<InputOrder>,
-}
-
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-enum InputOrder {
-    NewToOld,
-    OldToNew,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ImagesPageResponse {
-    images: Vec<ImageResponse>,
-    cursor: Option<i32>,
+    /// Comma separated tag names, images must have at least one of them.
+    any_tags: Option<String>,
+    /// Comma separated tag names, images must have all of them.
+    all_tags: Option<String>,
 }
 
 async fn get_images(
     Query(input): Query<ImagesPageInput>,
-    fip: impl FetchImagesPage,
+    image_mngr: impl FetchImagesPage + FetchImagesTags,
 ) -> Result<Json<ImagesPageResponse>, (StatusCode, String)> {
-    fip.fetch_images_page(
-        input.count,
-        input.after,
-        match input.order {
-            Some(InputOrder::NewToOld) => PaginationOrder::NewToOld,
-            Some(InputOrder::OldToNew) => PaginationOrder::OldToNew,
-            None => PaginationOrder::OldToNew,
-        },
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
-    .map(|v| {
-        Json(ImagesPageResponse {
-            cursor: v.cursor,
-            images: v.images.into_iter().map(|v| v.into()).collect(),
-        })
-    })
+    let tag_filter = TagFilter {
+        any_of: input
+            .any_tags
+            .as_deref()
+            .map(split_tag_names)
+            .unwrap_or_default(),
+        all_of: input
+            .all_tags
+            .as_deref()
+            .map(split_tag_names)
+            .unwrap_or_default(),
+    };
+    let page = image_mngr
+        .fetch_images_page(input.count, input.after, input.order.into(), &tag_filter)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    ImagesPageResponse::with_tags(page, image_mngr)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map(Json)
 }
@@ -1,10 +1,12 @@
+use std::io;
+
 use axum::{routing::post, Json, Router};
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 
 use crate::{
-    api::{routing::ApiError, IMAGES_DIR},
+    api::{image_derivatives, routing::ApiError, IMAGES_DIR, PENDING_DIR},
     domain::{actions::image::RenameImage, screensaver::Screensaver},
 };
 
@@ -20,7 +22,6 @@ pub fn make_rename_router(
 enum RenameImageError {
     Fs(String),
     Persistence(String),
-    FailedToRenameInQueue,
 }
 
 impl ApiError for RenameImageError {}
@@ -44,6 +45,15 @@ async fn rename_image(
         )
     })?;
 
+    rename_saved(&input, ri, &mut screensaver).await
+}
+
+/// Renames the saved image, then the image in the screensaver.
+async fn rename_saved(
+    input: &RenameInput,
+    ri: impl RenameImage,
+    screensaver: &mut impl Screensaver,
+) -> Result<(), (StatusCode, String)> {
     ri.rename_image(&input.old_name, &input.new_name)
         .await
         .map_err(|e| {
@@ -53,23 +63,83 @@ async fn rename_image(
             )
         })?;
 
-    screensaver
-        .rename_image(&input.old_name, &input.new_name)
-        .map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                RenameImageError::FailedToRenameInQueue.to_json_string(),
-            )
-        })?;
+    // Images excluded from the screensaver aren't in it.
+    let _ = screensaver.rename_image(&input.old_name, &input.new_name);
 
     Ok(())
 }
 
 async fn rename_fs(input: &RenameInput) -> Result<(), String> {
-    fs::rename(
-        format!("{}/{}", IMAGES_DIR, input.old_name),
-        format!("{}/{}", IMAGES_DIR, input.new_name),
-    )
-    .await
-    .map_err(|e| e.to_string())
+    // Images pending approval are in their own directory.
+    let rename_in = |dir| {
+        fs::rename(
+            format!("{}/{}", dir, input.old_name),
+            format!("{}/{}", dir, input.new_name),
+        )
+    };
+    match rename_in(IMAGES_DIR).await {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => rename_in(PENDING_DIR).await,
+        res => res,
+    }
+    .map_err(|e| e.to_string())?;
+
+    // Derivatives that failed to move are generated again when they're requested.
+    // The ones left behind are removed by the next canon update.
+    let (old_name, new_name) = (input.old_name.clone(), input.new_name.clone());
+    let res = tokio::task::spawn_blocking(move || {
+        image_derivatives::rename_derivatives(&old_name, &new_name)
+    })
+    .await;
+    if let Err(e) = res
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()))
+    {
+        tracing::warn!("failed to rename derivatives of {}: {}", input.old_name, e);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{domain::models::Image, state::screensaver_manager::ScreensaverManager};
+
+    struct SavedImages;
+
+    #[async_trait]
+    impl RenameImage for SavedImages {
+        async fn rename_image(&self, _: &str, _: &str) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn rename_saved_accepts_image_excluded_from_screensaver() {
+        // Arrange
+        let img = Image {
+            file_name: "shown.png".to_string(),
+            width: 1,
+            height: 1,
+        };
+        let mut screensaver = ScreensaverManager::restore(
+            Vec::new(),
+            HashMap::from([(img.file_name.clone(), img.clone())]),
+        );
+        let input = RenameInput {
+            old_name: "excluded.png".to_string(),
+            new_name: "renamed.png".to_string(),
+        };
+
+        // Act
+        let res = rename_saved(&input, SavedImages, &mut screensaver).await;
+
+        // Assert
+        assert!(res.is_ok());
+        assert_eq!(Some(img), screensaver.current());
+    }
 }
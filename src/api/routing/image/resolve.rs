@@ -1,10 +1,10 @@
 use axum::{routing::post, Json, Router};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::screensaver::{ResolveState, Screensaver};
+use crate::domain::screensaver::{DeviceScreensaver, ResolveState};
 
 pub fn make_resolve_router(
-    screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+    screensaver: impl 'static + Clone + Send + Sync + DeviceScreensaver,
 ) -> Router {
     Router::new().route(
         "/resolve",
@@ -16,6 +16,7 @@ pub fn make_resolve_router(
 #[serde(rename_all = "camelCase")]
 struct ResolveInput {
     file_name: String,
+    device_id: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -34,9 +35,9 @@ enum ResolveStatus {
 
 fn resolve(
     Json(input): Json<ResolveInput>,
-    mut screensaver: impl Screensaver,
+    mut screensaver: impl DeviceScreensaver,
 ) -> Json<ResolveResponse> {
-    let x = screensaver.resolve(&input.file_name);
+    let x = screensaver.resolve_for_device(input.device_id.as_deref(), &input.file_name);
     Json(ResolveResponse {
         resolve_status: match x {
             ResolveState::NotCurrent => ResolveStatus::NotCurrent,
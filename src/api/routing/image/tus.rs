@@ -0,0 +1,694 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use axum::{
+    extract::{self, BodyStream, OriginalUri},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{head, post},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Duration, TimeZone, Utc};
+use futures::{future, TryStreamExt};
+use hyper::{
+    header::{CACHE_CONTROL, CONTENT_TYPE, LOCATION},
+    HeaderMap, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt,
+};
+use tokio_util::io::StreamReader;
+
+use crate::{
+    api::{
+        content_hash, ingest_limits::IngestLimits, path_is_valid, routing::ApiError,
+        RESUMABLE_UPLOADS_DIR,
+    },
+    domain::{
+        actions::image::{DeleteImage, FetchImage, FetchImageByContentHash, SaveImage},
+        models::{Image, UploadDetails},
+        screensaver::Screensaver,
+    },
+};
+
+use super::upload::{self, StagedFile, UploadImageError};
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,expiration,termination";
+const PATCH_CONTENT_TYPE: &str = "application/offset+octet-stream";
+/// How long an unfinished upload is kept after it was last appended to.
+const UPLOAD_EXPIRY_HOURS: i64 = 24;
+
+/// Implements the tus resumable upload protocol, see https://tus.io/protocols/resumable-upload.
+/// Finished uploads are saved like those of `/upload`.
+pub fn make_tus_router(
+    image_mngr: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + DeleteImage
+        + FetchImage
+        + FetchImageByContentHash
+        + SaveImage,
+    screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+) -> Router {
+    let limits = IngestLimits::from_env();
+    let uploads = ActiveUploads::default();
+
+    Router::new()
+        .route(
+            "/tus",
+            post({
+                let (image_mngr, limits, uploads) =
+                    (image_mngr.clone(), limits.clone(), uploads.clone());
+                |uri, headers| create_upload(uri, headers, image_mngr, limits, uploads)
+            })
+            .options({
+                let limits = limits.clone();
+                || describe_server(limits)
+            }),
+        )
+        .route(
+            "/tus/:id",
+            head(fetch_offset)
+                .patch({
+                    let uploads = uploads.clone();
+                    |id, headers, body| {
+                        append_to_upload(
+                            id,
+                            headers,
+                            body,
+                            image_mngr,
+                            screensaver,
+                            limits,
+                            uploads,
+                        )
+                    }
+                })
+                .delete(|id, headers| terminate_upload(id, headers, uploads)),
+        )
+        .layer(middleware::map_response(add_tus_headers))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum TusError {
+    #[serde(rename_all = "camelCase")]
+    UnsupportedVersion {
+        supported: String,
+    },
+    MissingUploadLength,
+    InvalidUploadLength,
+    InvalidUploadMetadata,
+    MissingFileName,
+    InvalidFileName,
+    MissingUploadOffset,
+    InvalidUploadOffset,
+    #[serde(rename_all = "camelCase")]
+    OffsetMismatch {
+        upload_offset: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    WrongContentType {
+        expected: String,
+    },
+    ExceedsUploadLength,
+    UploadNotFound,
+    UploadLocked,
+    Upload(UploadImageError),
+    GeneralError(String),
+}
+
+impl ApiError for TusError {}
+
+/// What's known of an unfinished upload, saved next to its data.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadInfo {
+    file_name: String,
+    length: u64,
+    /// A Unix timestamp in seconds.
+    expires_at: i64,
+    /// Missing from the info of uploads created before batches were tracked.
+    #[serde(default)]
+    upload_batch: Option<String>,
+}
+
+impl UploadInfo {
+    fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now().timestamp()
+    }
+
+    fn extend_expiry(&mut self) {
+        self.expires_at = (Utc::now() + Duration::hours(UPLOAD_EXPIRY_HOURS)).timestamp();
+    }
+
+    /// Formats the expiry as an HTTP date, like `Wed, 25 Jun 2014 16:00:00 GMT`.
+    fn expires_header(&self) -> String {
+        Utc.timestamp_opt(self.expires_at, 0)
+            .single()
+            .unwrap_or_default()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string()
+    }
+}
+
+/// The ids of uploads being appended to, finished or terminated. Each id is held by one request
+/// at a time, so requests don't interleave writes.
+#[derive(Clone, Default)]
+struct ActiveUploads(Arc<Mutex<HashSet<String>>>);
+
+impl ActiveUploads {
+    /// Holds the id until the returned guard is dropped.
+    /// Returns `None` if another request holds it.
+    fn hold(&self, id: &str) -> Option<ActiveUploadGuard> {
+        if self.acquire_lock().insert(id.to_string()) {
+            Some(ActiveUploadGuard {
+                uploads: self.clone(),
+                id: id.to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn is_held(&self, id: &str) -> bool {
+        self.acquire_lock().contains(id)
+    }
+
+    /// In this case, we don't care if the mutex is poisoned, as the set is only modified by
+    /// single inserts and removes.
+    fn acquire_lock(&self) -> MutexGuard<'_, HashSet<String>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poison) => {
+                tracing::debug!("Accessing poisoned mutex");
+                poison.into_inner()
+            }
+        }
+    }
+}
+
+struct ActiveUploadGuard {
+    uploads: ActiveUploads,
+    id: String,
+}
+
+impl Drop for ActiveUploadGuard {
+    fn drop(&mut self) {
+        self.uploads.acquire_lock().remove(&self.id);
+    }
+}
+
+async fn add_tus_headers(mut response: Response) -> Response {
+    let status = response.status();
+    let headers = response.headers_mut();
+    headers.insert("tus-resumable", TUS_VERSION.parse().expect("valid header"));
+    if status == StatusCode::PRECONDITION_FAILED {
+        headers.insert("tus-version", TUS_VERSION.parse().expect("valid header"));
+    }
+    response
+}
+
+async fn describe_server(limits: IngestLimits) -> impl IntoResponse {
+    (
+        StatusCode::NO_CONTENT,
+        [
+            ("tus-version", TUS_VERSION.to_string()),
+            ("tus-extension", TUS_EXTENSIONS.to_string()),
+            ("tus-max-size", limits.max_byte_size.to_string()),
+        ],
+    )
+}
+
+async fn create_upload(
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    fi: impl FetchImage,
+    limits: IngestLimits,
+    uploads: ActiveUploads,
+) -> Result<Response, (StatusCode, String)> {
+    check_version(&headers)?;
+
+    let length = header_u64(&headers, "upload-length")
+        .map_err(|_| bad_request(TusError::InvalidUploadLength))?
+        .ok_or_else(|| bad_request(TusError::MissingUploadLength))?;
+    if length > limits.max_byte_size {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            TusError::Upload(UploadImageError::FileTooLarge {
+                max_byte_size: limits.max_byte_size,
+            })
+            .to_json_string(),
+        ));
+    }
+
+    let metadata = headers
+        .get("upload-metadata")
+        .map(|v| v.to_str().ok().and_then(parse_metadata))
+        .unwrap_or_else(|| Some(HashMap::new()))
+        .ok_or_else(|| bad_request(TusError::InvalidUploadMetadata))?;
+    let file_name = metadata
+        .get("filename")
+        .cloned()
+        .ok_or_else(|| bad_request(TusError::MissingFileName))?;
+    if !path_is_valid(&file_name) {
+        return Err(bad_request(TusError::InvalidFileName));
+    }
+    upload::reject_existing_file_name(&file_name, &fi)
+        .await
+        .map_err(|(s, e)| (s, TusError::Upload(e).to_json_string()))?;
+    let upload_batch =
+        upload::upload_batch_from(&headers).map_err(|e| bad_request(TusError::Upload(e)))?;
+
+    if let Err(e) = tokio::task::spawn_blocking(move || remove_expired_uploads(&uploads)).await {
+        tracing::warn!("failed to remove expired uploads: {}", e);
+    }
+
+    let id = format!("{:032x}", rand::random::<u128>());
+    let mut info = UploadInfo {
+        file_name,
+        length,
+        expires_at: 0,
+        upload_batch: Some(upload_batch.clone()),
+    };
+    info.extend_expiry();
+    // The info is written first, so data without info is always left over from a finished upload.
+    create_upload_files(&id, &info)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    let location = format!("{}/{}", uri.path().trim_end_matches('/'), id);
+    Ok((
+        StatusCode::CREATED,
+        [
+            (LOCATION.as_str(), location),
+            ("upload-expires", info.expires_header()),
+            (upload::UPLOAD_BATCH_HEADER, upload_batch),
+        ],
+    )
+        .into_response())
+}
+
+async fn fetch_offset(
+    extract::Path(id): extract::Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    check_version(&headers)?;
+
+    let info = read_info(&id).await?;
+    let offset = upload_offset(&id).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("upload-offset", offset.to_string()),
+            ("upload-length", info.length.to_string()),
+            ("upload-expires", info.expires_header()),
+            (CACHE_CONTROL.as_str(), "no-store".to_string()),
+        ],
+    )
+        .into_response())
+}
+
+async fn append_to_upload(
+    extract::Path(id): extract::Path<String>,
+    headers: HeaderMap,
+    body: BodyStream,
+    image_mngr: impl DeleteImage + FetchImage + FetchImageByContentHash + SaveImage,
+    mut screensaver: impl Screensaver,
+    limits: IngestLimits,
+    uploads: ActiveUploads,
+) -> Result<Response, (StatusCode, String)> {
+    check_version(&headers)?;
+    let content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    if content_type != Some(PATCH_CONTENT_TYPE) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            TusError::WrongContentType {
+                expected: PATCH_CONTENT_TYPE.to_string(),
+            }
+            .to_json_string(),
+        ));
+    }
+    let client_offset = header_u64(&headers, "upload-offset")
+        .map_err(|_| bad_request(TusError::InvalidUploadOffset))?
+        .ok_or_else(|| bad_request(TusError::MissingUploadOffset))?;
+
+    let _guard = uploads
+        .hold(&id)
+        .ok_or_else(|| (StatusCode::LOCKED, TusError::UploadLocked.to_json_string()))?;
+    let mut info = read_info(&id).await?;
+    let offset = upload_offset(&id).await?;
+    if client_offset != offset {
+        return Err((
+            StatusCode::CONFLICT,
+            TusError::OffsetMismatch {
+                upload_offset: offset,
+            }
+            .to_json_string(),
+        ));
+    }
+
+    let offset = append_body(&id, offset, info.length, body).await?;
+    info.extend_expiry();
+
+    if offset < info.length {
+        write_info(&id, &info)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+    } else {
+        let details = UploadDetails {
+            upload_batch: info.upload_batch.clone(),
+            ..Default::default()
+        };
+        let image = finish_upload(&id, info.file_name.clone(), &details, &image_mngr, &limits)
+            .await
+            .map_err(|(s, e)| (s, TusError::Upload(e).to_json_string()))?;
+        let file_name = image.file_name.clone();
+        if screensaver.insert(image).is_err() {
+            tracing::warn!("uploaded image {} already in the screensaver", file_name);
+        }
+    }
+
+    Ok((
+        StatusCode::NO_CONTENT,
+        [
+            ("upload-offset", offset.to_string()),
+            ("upload-expires", info.expires_header()),
+        ],
+    )
+        .into_response())
+}
+
+async fn terminate_upload(
+    extract::Path(id): extract::Path<String>,
+    headers: HeaderMap,
+    uploads: ActiveUploads,
+) -> Result<StatusCode, (StatusCode, String)> {
+    check_version(&headers)?;
+
+    let _guard = uploads
+        .hold(&id)
+        .ok_or_else(|| (StatusCode::LOCKED, TusError::UploadLocked.to_json_string()))?;
+    read_info(&id).await?;
+    remove_upload_files(&id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Appends the body to the upload's data, returning the new offset. Data received before the
+/// body is cut off is kept, so the upload can be resumed from it.
+async fn append_body(
+    id: &str,
+    offset: u64,
+    length: u64,
+    body: BodyStream,
+) -> Result<u64, (StatusCode, String)> {
+    let path = data_path(id);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    let remaining = length - offset;
+    let mut received = 0;
+    let res = async {
+        let body_with_io_error = body.map_err(io::Error::other).and_then(|bytes| {
+            received += bytes.len() as u64;
+            let res = if received > remaining {
+                Err(io::Error::other("upload length exceeded"))
+            } else {
+                Ok(bytes)
+            };
+            future::ready(res)
+        });
+        let body_reader = StreamReader::new(body_with_io_error);
+        futures::pin_mut!(body_reader);
+        tokio::io::copy(&mut body_reader, &mut file).await?;
+        Ok::<_, io::Error>(())
+    }
+    .await;
+    let flushed = file.flush().await;
+
+    if received > remaining {
+        // The data past the length is dropped, so the upload can be resumed with a valid body.
+        file.set_len(offset)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        return Err(bad_request(TusError::ExceedsUploadLength));
+    }
+    res.and(flushed)
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    upload_offset(id).await
+}
+
+/// Saves the finished upload like any other upload. The upload is removed either way.
+async fn finish_upload(
+    id: &str,
+    file_name: String,
+    details: &UploadDetails,
+    image_mngr: &(impl DeleteImage + FetchImage + FetchImageByContentHash + SaveImage),
+    limits: &IngestLimits,
+) -> Result<Image, (StatusCode, UploadImageError)> {
+    let general_error = |e: String| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            UploadImageError::GeneralError(e),
+        )
+    };
+
+    let staged_file = StagedFile::adopt(data_path(id));
+    if let Err(e) = fs::remove_file(info_path(id)).await {
+        tracing::warn!("failed to remove info of finished upload {}: {}", id, e);
+    }
+
+    let path = data_path(id);
+    let (content_hash, byte_size) = tokio::task::spawn_blocking(move || {
+        let content_hash = content_hash::fetch_content_hash_at(&path)?;
+        Ok::<_, io::Error>((content_hash, std::fs::metadata(&path)?.len()))
+    })
+    .await
+    .map_err(|e| general_error(e.to_string()))?
+    .map_err(|e| general_error(e.to_string()))?;
+
+    // Another upload may have taken the name since this one was created.
+    upload::reject_existing_file_name(&file_name, image_mngr).await?;
+    upload::ingest_staged(
+        file_name,
+        staged_file,
+        content_hash,
+        byte_size,
+        image_mngr,
+        details,
+        limits,
+    )
+    .await
+}
+
+/// Removes expired uploads and data left over from uploads that were being finished during a
+/// shutdown. Uploads held by a request are skipped.
+fn remove_expired_uploads(uploads: &ActiveUploads) {
+    let entries = match std::fs::read_dir(RESUMABLE_UPLOADS_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+        Err(e) => {
+            tracing::warn!("failed to read the resumable uploads directory: {}", e);
+            return;
+        }
+    };
+
+    let ids: HashSet<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.split('.').next().map(str::to_string)
+        })
+        .collect();
+    for id in ids {
+        if uploads.is_held(&id) {
+            continue;
+        }
+        let info = std::fs::read(info_path(&id))
+            .ok()
+            .and_then(|info| serde_json::from_slice::<UploadInfo>(&info).ok());
+        // Data without info is left over from an upload that was being finished.
+        let is_expired = match info {
+            Some(info) => info.is_expired(),
+            None => true,
+        };
+        if is_expired {
+            for path in [info_path(&id), data_path(&id)] {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        tracing::warn!("failed to remove expired upload {:?}: {}", path, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn create_upload_files(id: &str, info: &UploadInfo) -> io::Result<()> {
+    fs::create_dir_all(RESUMABLE_UPLOADS_DIR).await?;
+    write_info(id, info).await?;
+    fs::File::create(data_path(id)).await?;
+    Ok(())
+}
+
+async fn remove_upload_files(id: &str) -> io::Result<()> {
+    fs::remove_file(info_path(id)).await?;
+    match fs::remove_file(data_path(id)).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+async fn write_info(id: &str, info: &UploadInfo) -> io::Result<()> {
+    let info = serde_json::to_vec(info).map_err(io::Error::other)?;
+    // Written to a temporary file first, so the info is never read half written.
+    let tmp_path = info_path(id).with_extension("json.tmp");
+    fs::write(&tmp_path, info).await?;
+    fs::rename(&tmp_path, info_path(id)).await
+}
+
+/// Reads the info of an unfinished upload. Expired uploads are removed and treated as missing.
+async fn read_info(id: &str) -> Result<UploadInfo, (StatusCode, String)> {
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            TusError::UploadNotFound.to_json_string(),
+        )
+    };
+    if !is_upload_id(id) {
+        return Err(not_found());
+    }
+
+    let info = match fs::read(info_path(id)).await {
+        Ok(info) => info,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => return Err(internal_error(e.to_string())),
+    };
+    let info: UploadInfo =
+        serde_json::from_slice(&info).map_err(|e| internal_error(e.to_string()))?;
+    if info.is_expired() {
+        if let Err(e) = remove_upload_files(id).await {
+            tracing::warn!("failed to remove expired upload {}: {}", id, e);
+        }
+        return Err(not_found());
+    }
+
+    Ok(info)
+}
+
+async fn upload_offset(id: &str) -> Result<u64, (StatusCode, String)> {
+    fs::metadata(data_path(id))
+        .await
+        .map(|m| m.len())
+        .map_err(|e| internal_error(e.to_string()))
+}
+
+fn check_version(headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    if headers.get("tus-resumable").and_then(|v| v.to_str().ok()) == Some(TUS_VERSION) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::PRECONDITION_FAILED,
+            TusError::UnsupportedVersion {
+                supported: TUS_VERSION.to_string(),
+            }
+            .to_json_string(),
+        ))
+    }
+}
+
+/// Parses the header as a number. Returns `Ok(None)` if it's missing.
+fn header_u64(headers: &HeaderMap, name: &str) -> Result<Option<u64>, ()> {
+    headers
+        .get(name)
+        .map(|v| v.to_str().ok().and_then(|v| v.parse().ok()).ok_or(()))
+        .transpose()
+}
+
+/// Parses `Upload-Metadata`, a comma separated list of keys, each followed by a space and its
+/// base64 encoded value. Keys without a value map to an empty string.
+fn parse_metadata(header: &str) -> Option<HashMap<String, String>> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once(' ').unwrap_or((pair, ""));
+            let value = STANDARD.decode(value.trim()).ok()?;
+            Some((key.to_string(), String::from_utf8(value).ok()?))
+        })
+        .collect()
+}
+
+/// Ids are generated, so anything else can't refer to an upload. This also keeps them from
+/// escaping [RESUMABLE_UPLOADS_DIR].
+fn is_upload_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn data_path(id: &str) -> PathBuf {
+    Path::new(RESUMABLE_UPLOADS_DIR).join(id)
+}
+
+fn info_path(id: &str) -> PathBuf {
+    Path::new(RESUMABLE_UPLOADS_DIR).join(format!("{}.json", id))
+}
+
+fn bad_request(err: TusError) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, err.to_json_string())
+}
+
+fn internal_error(err: String) -> (StatusCode, String) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        TusError::GeneralError(err).to_json_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_metadata_decodes_values() {
+        // Arrange
+        let header = "filename cGFuby5qcGc=,is_confidential";
+
+        // Act
+        let res = parse_metadata(header);
+
+        // Assert
+        let expected = HashMap::from([
+            ("filename".to_string(), "pano.jpg".to_string()),
+            ("is_confidential".to_string(), String::new()),
+        ]);
+        assert_eq!(Some(expected), res);
+    }
+
+    #[test]
+    fn parse_metadata_rejects_invalid_base64() {
+        assert_eq!(None, parse_metadata("filename not-base64!"));
+    }
+
+    #[test]
+    fn is_upload_id_rejects_paths() {
+        assert!(is_upload_id("0123456789abcdef0123456789abcdef"));
+        assert!(!is_upload_id("../../images/0123456789abcdef0123"));
+    }
+}
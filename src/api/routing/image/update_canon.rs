@@ -1,30 +1,139 @@
-use axum::{routing::post, Router};
+use axum::{
+    extract::Query,
+    routing::{get, post},
+    Json, Router,
+};
 use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     api::{
-        canon::{self, UpdateCanonError},
+        canon::{self, CanonMode, CanonUpdates, SkippedEntry, UpdateCanonError},
+        ingest_limits::IngestLimits,
         routing::ApiError,
     },
-    domain::{actions::image::UpdateCanon, screensaver::Screensaver},
+    domain::{
+        actions::image::{
+            DiffCanon, FetchImageFingerprints, FetchScreensaverExclusions, UpdateCanon,
+        },
+        models::{CanonDiff, DimensionChange},
+        screensaver::Screensaver,
+    },
 };
 
 pub fn make_update_canon_router(
-    uc: impl 'static + Clone + Send + Sync + UpdateCanon,
+    uc: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + DiffCanon
+        + FetchImageFingerprints
+        + FetchScreensaverExclusions
+        + UpdateCanon,
     screensaver: impl 'static + Clone + Send + Sync + Screensaver,
 ) -> Router {
-    Router::new().route("/update_canon", post(|| update_canon(uc, screensaver)))
+    let limits = IngestLimits::from_env();
+    let updates = CanonUpdates::new(limits.clone());
+
+    Router::new()
+        .route(
+            "/diff_canon",
+            get({
+                let uc = uc.clone();
+                |query| diff_canon(query, uc, limits)
+            }),
+        )
+        .route(
+            "/update_canon",
+            post(|query| update_canon(query, uc, screensaver, updates)),
+        )
 }
 
 impl ApiError for UpdateCanonError {}
 
-async fn update_canon(
-    uc: impl UpdateCanon,
-    mut screensaver: impl Screensaver,
-) -> Result<(), (StatusCode, String)> {
-    canon::update_canon(&uc, &mut screensaver)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateCanonInput {
+    /// [CanonMode::Strict] when it's unset.
+    mode: Option<CanonMode>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateCanonResponse {
+    skipped: Vec<SkippedEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CanonDiffResponse {
+    /// The file names of the images that would be saved.
+    inserts: Vec<String>,
+    /// The file names of the saved images that would be deleted.
+    deletes: Vec<String>,
+    dimension_changes: Vec<DimensionChangeResponse>,
+    skipped: Vec<SkippedEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DimensionChangeResponse {
+    file_name: String,
+    saved_width: u32,
+    saved_height: u32,
+    width: u32,
+    height: u32,
+}
+
+impl From<DimensionChange> for DimensionChangeResponse {
+    fn from(value: DimensionChange) -> Self {
+        Self {
+            file_name: value.file_name,
+            saved_width: value.saved.0,
+            saved_height: value.saved.1,
+            width: value.current.0,
+            height: value.current.1,
+        }
+    }
+}
+
+// Dry run of `/update_canon`. Nothing is saved and no entry is moved to quarantine.
+async fn diff_canon(
+    Query(input): Query<UpdateCanonInput>,
+    dc: impl DiffCanon + FetchImageFingerprints,
+    limits: IngestLimits,
+) -> Result<Json<CanonDiffResponse>, (StatusCode, String)> {
+    let (diff, skipped) = canon::diff_canon(&dc, input.mode.unwrap_or_default(), &limits)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_json_string()))?;
+    let CanonDiff {
+        inserts,
+        deletes,
+        dimension_changes,
+    } = diff;
+
+    Ok(Json(CanonDiffResponse {
+        inserts,
+        deletes,
+        dimension_changes: dimension_changes.into_iter().map(|v| v.into()).collect(),
+        skipped,
+    }))
+}
 
-    Ok(())
+// Requests made while an update with the same mode runs get the response of that update.
+async fn update_canon(
+    Query(input): Query<UpdateCanonInput>,
+    uc: impl 'static + Send + Sync + UpdateCanon + FetchImageFingerprints + FetchScreensaverExclusions,
+    screensaver: impl 'static + Send + Screensaver,
+    updates: CanonUpdates,
+) -> Result<Json<UpdateCanonResponse>, (StatusCode, String)> {
+    let outcome = updates
+        .update_canon(uc, screensaver, input.mode.unwrap_or_default())
+        .await;
+    match outcome.as_ref() {
+        Ok(skipped) => Ok(Json(UpdateCanonResponse {
+            skipped: skipped.clone(),
+        })),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_json_string())),
+    }
 }
@@ -1,190 +1,754 @@
-use std::io;
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
 
 use axum::{
-    extract::{
-        multipart::{Field, MultipartError},
-        DefaultBodyLimit, Multipart,
-    },
-    routing::post,
-    BoxError, Router,
+    extract::{self, multipart::Field, BodyStream, DefaultBodyLimit, Multipart},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{post, put},
+    BoxError, Json, Router,
 };
-use futures::{Stream, TryStreamExt};
-use hyper::{body::Bytes, StatusCode};
+use futures::{future, Stream, TryStreamExt};
+use hyper::{body::Bytes, Body, HeaderMap, Method, Request, StatusCode};
 use serde::Serialize;
-use tokio::{fs::File, io::BufWriter};
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+};
 use tokio_util::io::StreamReader;
 use tower_http::limit::RequestBodyLimitLayer;
 
 use crate::{
     api::{
+        canon, content_hash, image_derivatives,
         image_dimensions::{self, FetchImageDimensionsError},
-        routing::ApiError,
-        IMAGES_DIR,
+        image_formats, image_metadata, image_orientation,
+        ingest_limits::IngestLimits,
+        path_is_valid, perceptual_hash,
+        routing::{
+            image::{
+                upload_details::{self, UploadDetailsError},
+                ImageResponse,
+            },
+            ApiError,
+        },
+        IMAGES_DIR, PENDING_DIR, UPLOAD_STAGING_DIR,
     },
     domain::{
-        actions::image::{FetchImage, SaveImage},
-        models::Image,
+        actions::image::{
+            DeleteImage, FetchImage, FetchImageByContentHash, SaveImage, SaveImageError,
+        },
+        models::{Image, ImageFile, UploadDetails},
         screensaver::Screensaver,
     },
 };
 
 pub fn make_upload_router(
-    image_mngr: impl 'static + Clone + Send + Sync + FetchImage + SaveImage,
+    image_mngr: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + DeleteImage
+        + FetchImage
+        + FetchImageByContentHash
+        + SaveImage,
+    screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+) -> Router {
+    clear_staging_dir();
+    upload_routes(image_mngr, screensaver, IngestLimits::from_env())
+}
+
+fn upload_routes(
+    image_mngr: impl 'static
+        + Clone
+        + Send
+        + Sync
+        + DeleteImage
+        + FetchImage
+        + FetchImageByContentHash
+        + SaveImage,
     screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+    limits: IngestLimits,
 ) -> Router {
+    let body_limit = request_body_limit(&limits);
+
     Router::new()
         .route(
             "/upload",
-            post(|body| upload_image(body, image_mngr, screensaver)),
+            post({
+                let (image_mngr, screensaver, limits) =
+                    (image_mngr.clone(), screensaver.clone(), limits.clone());
+                |headers, body| upload_image(headers, body, image_mngr, screensaver, limits)
+            }),
+        )
+        .route(
+            "/:file_name",
+            put(|file_name, headers, body| {
+                put_image(file_name, headers, body, image_mngr, screensaver, limits)
+            }),
         )
         .layer(DefaultBodyLimit::disable())
-        .layer(RequestBodyLimitLayer::new(
-            250 * 1024 * 1024, /* 250mb */
-        ))
+        .layer(RequestBodyLimitLayer::new(body_limit))
 }
 
+const REQUEST_BODY_LIMIT: usize = 250 * 1024 * 1024; /* 250mb */
+/// Leaves room for the multipart framing, so files over the limit get a structured error.
+pub(super) fn request_body_limit(limits: &IngestLimits) -> usize {
+    REQUEST_BODY_LIMIT.max(limits.max_byte_size as usize + 1024 * 1024)
+}
+
+/// Names the batch of an upload. Requests without it get a new batch, named in the response.
+pub(super) const UPLOAD_BATCH_HEADER: &str = "upload-batch";
+const MAX_UPLOAD_BATCH_LEN: usize = 64;
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-enum UploadImageError {
+pub(super) enum UploadImageError {
     FileFieldErr(FileFieldValidationError),
+    InvalidDetails(UploadDetailsError),
+    #[serde(rename_all = "camelCase")]
+    InvalidUploadBatch {
+        max_len: usize,
+    },
+    InvalidFileName,
+    /// The file name is the path of another route, so `PUT /:file_name` can't be reached with it.
+    ReservedFileName,
     ImageAlreadyExists,
+    #[serde(rename_all = "camelCase")]
+    DuplicateContent {
+        existing_file_name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    FileTooLarge {
+        max_byte_size: u64,
+    },
+    UnrecognizedFormat,
+    #[serde(rename_all = "camelCase")]
+    FormatNotAllowed {
+        format: String,
+        allowed_formats: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    TooManyPixels {
+        width: u32,
+        height: u32,
+        max_megapixels: f64,
+    },
     FailedToFetchDimensions(FetchImageDimensionsError),
-    FailedToInsertImage,
+    AlbumNotFound,
+    GuestLinkNotFound,
+    GuestLinkExpired,
+    #[serde(rename_all = "camelCase")]
+    GuestLinkLimitReached {
+        max_uploads: u32,
+        max_byte_size: u64,
+    },
     GeneralError(String),
 }
 
 impl ApiError for UploadImageError {}
 
-// Handler that accepts a multipart form upload and streams each field to a file.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct UploadResult {
+    /// `None` when the part has no file name.
+    pub(super) file_name: Option<String>,
+    #[serde(flatten)]
+    pub(super) outcome: UploadOutcome,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "status", content = "error")]
+pub(super) enum UploadOutcome {
+    Ok,
+    AlreadyExists(UploadImageError),
+    Invalid(UploadImageError),
+    Error(UploadImageError),
+}
+
+impl UploadOutcome {
+    pub(super) fn from_error((status, err): (StatusCode, UploadImageError)) -> Self {
+        match err {
+            UploadImageError::ImageAlreadyExists | UploadImageError::DuplicateContent { .. } => {
+                Self::AlreadyExists(err)
+            }
+            _ if status.is_client_error() => Self::Invalid(err),
+            _ => Self::Error(err),
+        }
+    }
+}
+
+// Handler that accepts a multipart form upload and streams each file part to a file.
+// Every part is uploaded independently, with an outcome per part. Parts without a file set the
+// details of the files after them, see [upload_details::read_details_field].
 async fn upload_image(
+    headers: HeaderMap,
     mut multipart: Multipart,
-    image_mngr: impl FetchImage + SaveImage,
+    image_mngr: impl DeleteImage + FetchImage + FetchImageByContentHash + SaveImage,
     mut screensaver: impl Screensaver,
-) -> Result<(), (StatusCode, String)> {
-    let (file_name, file_field) = validate_field(multipart.next_field().await).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            UploadImageError::FileFieldErr(e).to_json_string(),
-        )
-    })?;
+    limits: IngestLimits,
+) -> Result<([(&'static str, String); 1], Json<Vec<UploadResult>>), (StatusCode, String)> {
+    let upload_batch =
+        upload_batch_from(&headers).map_err(|e| (StatusCode::BAD_REQUEST, e.to_json_string()))?;
 
-    let existing_image = image_mngr.fetch_image(&file_name).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            UploadImageError::GeneralError(e).to_json_string(),
+    let mut results = Vec::new();
+    let mut uploaded = HashMap::new();
+    let mut details = UploadDetails {
+        upload_batch: Some(upload_batch.clone()),
+        ..Default::default()
+    };
+    // Files after an invalid details field aren't uploaded, since they'd miss the details.
+    let mut details_error = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                // The rest of the body can't be read once a part is malformed.
+                results.push(UploadResult {
+                    file_name: None,
+                    outcome: UploadOutcome::Invalid(UploadImageError::FileFieldErr(
+                        FileFieldValidationError::FieldErr(e.to_string()),
+                    )),
+                });
+                break;
+            }
+        };
+
+        let Some(file_name) = field.file_name().map(str::to_string) else {
+            if let Err(e) = upload_details::read_details_field(field, &mut details).await {
+                details_error = Some(e);
+            }
+            continue;
+        };
+
+        if let Some(e) = &details_error {
+            results.push(UploadResult {
+                file_name: Some(file_name),
+                outcome: UploadOutcome::Invalid(UploadImageError::InvalidDetails(e.clone())),
+            });
+            continue;
+        }
+
+        let outcome = match upload_file(field, &image_mngr, &details, &limits).await {
+            Ok(image) => {
+                if !details.excluded_from_screensaver {
+                    uploaded.insert(image.file_name.clone(), image);
+                }
+                UploadOutcome::Ok
+            }
+            Err(e) => UploadOutcome::from_error(e),
+        };
+        let file_name = Some(file_name);
+        results.push(UploadResult { file_name, outcome });
+    }
+
+    if results.is_empty() {
+        let err = match details_error {
+            Some(e) => UploadImageError::InvalidDetails(e),
+            None => UploadImageError::FileFieldErr(FileFieldValidationError::MissingField),
+        };
+        return Err((StatusCode::BAD_REQUEST, err.to_json_string()));
+    }
+
+    if let Err(contained) = screensaver.insert_many(uploaded.clone()) {
+        // Nothing is inserted when any image is already contained, so the rest are inserted again.
+        tracing::warn!(
+            "uploaded images already in the screensaver: {}",
+            contained.join(", ")
+        );
+        for file_name in contained {
+            uploaded.remove(&file_name);
+        }
+        if let Err(contained) = screensaver.insert_many(uploaded) {
+            tracing::error!(
+                "failed to insert uploaded images into the screensaver: {}",
+                contained.join(", ")
+            );
+        }
+    }
+
+    Ok(([(UPLOAD_BATCH_HEADER, upload_batch)], Json(results)))
+}
+
+/// Requests to `PUT /:file_name` with the name of another route are routed there, which doesn't
+/// accept `PUT`. They get a [UploadImageError::ReservedFileName] instead of a bare 405.
+pub(super) async fn reject_reserved_file_names(req: Request<Body>, next: Next<Body>) -> Response {
+    let is_file_name_put =
+        req.method() == Method::PUT && !req.uri().path().trim_start_matches('/').contains('/');
+    let res = next.run(req).await;
+    if is_file_name_put && res.status() == StatusCode::METHOD_NOT_ALLOWED {
+        return (
+            StatusCode::CONFLICT,
+            UploadImageError::ReservedFileName.to_json_string(),
         )
-    })?;
-    if existing_image.is_some() {
+            .into_response();
+    }
+
+    res
+}
+
+// Handler that takes the raw request body as the image, for clients like scripts that can't send
+// the multipart form.
+async fn put_image(
+    extract::Path(file_name): extract::Path<String>,
+    headers: HeaderMap,
+    body: BodyStream,
+    image_mngr: impl DeleteImage + FetchImage + FetchImageByContentHash + SaveImage,
+    mut screensaver: impl Screensaver,
+    limits: IngestLimits,
+) -> Result<(StatusCode, [(&'static str, String); 1], Json<ImageResponse>), (StatusCode, String)> {
+    if !path_is_valid(&file_name) {
         return Err((
             StatusCode::BAD_REQUEST,
-            UploadImageError::ImageAlreadyExists.to_json_string(),
+            UploadImageError::InvalidFileName.to_json_string(),
         ));
     }
+    let upload_batch =
+        upload_batch_from(&headers).map_err(|e| (StatusCode::BAD_REQUEST, e.to_json_string()))?;
+    let details = UploadDetails {
+        upload_batch: Some(upload_batch.clone()),
+        ..Default::default()
+    };
 
-    stream_to_file(&file_name, file_field)
+    let image = async {
+        reject_existing_file_name(&file_name, &image_mngr).await?;
+        let (staged_file, content_hash, byte_size) =
+            stage_upload(&file_name, limits.max_byte_size, body).await?;
+        ingest_staged(
+            file_name,
+            staged_file,
+            content_hash,
+            byte_size,
+            &image_mngr,
+            &details,
+            &limits,
+        )
         .await
-        .map_err(|(s, e)| (s, e.to_json_string()))?;
+    }
+    .await
+    .map_err(|(s, e)| (s, e.to_json_string()))?;
 
-    let (image_width, image_height) = image_dimensions::fetch_image_dimensions(&file_name)
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                UploadImageError::FailedToFetchDimensions(e).to_json_string(),
-            )
-        })?;
+    if screensaver.insert(image.clone()).is_err() {
+        tracing::warn!(
+            "uploaded image {} already in the screensaver",
+            image.file_name
+        );
+    }
 
-    tracing::debug!("image dimensions: {} x {}", image_width, image_height);
+    Ok((
+        StatusCode::CREATED,
+        [(UPLOAD_BATCH_HEADER, upload_batch)],
+        Json(image.into()),
+    ))
+}
 
-    let image = Image {
-        file_name,
-        width: image_width,
-        height: image_height,
+/// Reads the batch of the upload from the [UPLOAD_BATCH_HEADER], or names a new batch if it's
+/// missing. Names are short and limited to letters, digits, `-` and `_`.
+pub(super) fn upload_batch_from(headers: &HeaderMap) -> Result<String, UploadImageError> {
+    let Some(value) = headers.get(UPLOAD_BATCH_HEADER) else {
+        return Ok(format!("{:016x}", rand::random::<u64>()));
     };
+    match value.to_str() {
+        Ok(name)
+            if !name.is_empty()
+                && name.len() <= MAX_UPLOAD_BATCH_LEN
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Ok(name.to_string())
+        }
+        _ => Err(UploadImageError::InvalidUploadBatch {
+            max_len: MAX_UPLOAD_BATCH_LEN,
+        }),
+    }
+}
+
+/// Uploads the file of a multipart part with the given details, returning the saved image.
+async fn upload_file(
+    field: Field<'_>,
+    image_mngr: &(impl DeleteImage + FetchImage + FetchImageByContentHash + SaveImage),
+    details: &UploadDetails,
+    limits: &IngestLimits,
+) -> Result<Image, (StatusCode, UploadImageError)> {
+    let file_name = validate_field(&field)
+        .map_err(|e| (StatusCode::BAD_REQUEST, UploadImageError::FileFieldErr(e)))?;
+    reject_existing_file_name(&file_name, image_mngr).await?;
 
-    image_mngr.save_image(&image).await.map_err(|e| {
+    let (staged_file, content_hash, byte_size) =
+        stage_upload(&file_name, limits.max_byte_size, field).await?;
+    ingest_staged(
+        file_name,
+        staged_file,
+        content_hash,
+        byte_size,
+        image_mngr,
+        details,
+        limits,
+    )
+    .await
+}
+
+/// Validates a staged upload and saves it with its details, then moves it into [IMAGES_DIR] and
+/// generates its derivatives. Uploads through a guest link are moved into [PENDING_DIR] instead,
+/// until they're approved. Returns the saved image.
+pub(super) async fn ingest_staged(
+    file_name: String,
+    staged_file: StagedFile,
+    content_hash: String,
+    byte_size: u64,
+    image_mngr: &(impl DeleteImage + FetchImageByContentHash + SaveImage),
+    details: &UploadDetails,
+    limits: &IngestLimits,
+) -> Result<Image, (StatusCode, UploadImageError)> {
+    reject_duplicate_content(&content_hash, image_mngr).await?;
+
+    let image_file = tokio::task::spawn_blocking({
+        let path = staged_file.path.clone();
+        let limits = limits.clone();
+        move || {
+            let staged = StagedImage {
+                path: &path,
+                content_hash,
+                byte_size,
+            };
+            read_image_file(file_name, staged, &limits)
+        }
+    })
+    .await
+    .map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            UploadImageError::GeneralError(e).to_json_string(),
+            UploadImageError::GeneralError(e.to_string()),
         )
-    })?;
+    })??;
+
+    match image_mngr.save_image(&image_file, details).await {
+        Ok(()) => {}
+        Err(SaveImageError::AlbumNotFound) => {
+            return Err((StatusCode::BAD_REQUEST, UploadImageError::AlbumNotFound))
+        }
+        Err(SaveImageError::GuestLinkNotFound) => {
+            return Err((StatusCode::NOT_FOUND, UploadImageError::GuestLinkNotFound))
+        }
+        Err(SaveImageError::Persistence(e)) => {
+            // The same content may have been saved by a concurrent upload since it was checked.
+            reject_duplicate_content(&image_file.content_hash, image_mngr).await?;
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                UploadImageError::GeneralError(e),
+            ));
+        }
+    }
+
+    // Saved images of guest links are pending approval, see `SaveImage`.
+    let pending = details.guest_link.is_some();
+    let dir = if pending { PENDING_DIR } else { IMAGES_DIR };
+    if let Err(e) = staged_file.persist(dir, &image_file.image.file_name).await {
+        // Without its file the saved image would break the canon, so it's removed again.
+        if let Err(e) = image_mngr.delete_image(&image_file.image.file_name).await {
+            tracing::error!(
+                "failed to remove {} after failing to move it into place: {}",
+                image_file.image.file_name,
+                e
+            );
+        }
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            UploadImageError::GeneralError(e.to_string()),
+        ));
+    }
+
+    // Missing derivatives are generated when they're requested, so failing here is not fatal.
+    if pending {
+        return Ok(image_file.image);
+    }
+    let file_name = image_file.image.file_name.clone();
+    let res = tokio::task::spawn_blocking({
+        let (file_name, limits) = (file_name.clone(), limits.clone());
+        move || image_derivatives::generate_derivatives(&file_name, &limits)
+    })
+    .await;
+    match res {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("failed to generate derivatives of {}: {:?}", file_name, e),
+        Err(e) => tracing::warn!("failed to generate derivatives of {}: {}", file_name, e),
+    }
+
+    Ok(image_file.image)
+}
 
-    screensaver.insert(image).map_err(|_| {
+/// Rejects the upload if an image with the same name exists.
+pub(super) async fn reject_existing_file_name(
+    file_name: &str,
+    fi: &impl FetchImage,
+) -> Result<(), (StatusCode, UploadImageError)> {
+    let existing_image = fi.fetch_image(file_name).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            UploadImageError::FailedToInsertImage.to_json_string(),
+            UploadImageError::GeneralError(e),
         )
     })?;
+    match existing_image {
+        Some(_) => Err((
+            StatusCode::BAD_REQUEST,
+            UploadImageError::ImageAlreadyExists,
+        )),
+        None => Ok(()),
+    }
+}
 
-    Ok(())
+/// Rejects the upload if an image with the same content exists.
+async fn reject_duplicate_content(
+    content_hash: &str,
+    fic: &impl FetchImageByContentHash,
+) -> Result<(), (StatusCode, UploadImageError)> {
+    let existing_image = fic
+        .fetch_image_by_content_hash(content_hash)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                UploadImageError::GeneralError(e),
+            )
+        })?;
+    match existing_image {
+        Some(existing_image) => Err((
+            StatusCode::BAD_REQUEST,
+            UploadImageError::DuplicateContent {
+                existing_file_name: existing_image.file_name,
+            },
+        )),
+        None => Ok(()),
+    }
 }
 
-// Save a `Stream` to a file
-async fn stream_to_file<S, E>(
+// Save a `Stream` to a staged file, returning it with the hex encoded SHA-256 of its content and
+// its size. Streams over `max_byte_size` are cut off.
+pub(super) async fn stage_upload<S, E>(
     file_name: &str,
+    max_byte_size: u64,
     stream: S,
-) -> Result<(), (StatusCode, UploadImageError)>
+) -> Result<(StagedFile, String, u64), (StatusCode, UploadImageError)>
 where
     S: Stream<Item = Result<Bytes, E>>,
     E: Into<BoxError>,
 {
-    async {
-        // Convert the stream into an `AsyncRead`.
-        let body_with_io_error = stream.map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+    let general_error = |err: io::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            UploadImageError::GeneralError(err.to_string()),
+        )
+    };
+
+    let staged_file = StagedFile::new(file_name).await.map_err(general_error)?;
+    let mut hasher = Sha256::new();
+    let mut byte_size = 0;
+    let res = async {
+        // Convert the stream into an `AsyncRead`, hashing and counting the content as it's read.
+        let body_with_io_error = stream.map_err(io::Error::other).and_then(|bytes| {
+            byte_size += bytes.len() as u64;
+            let res = if byte_size > max_byte_size {
+                Err(io::Error::other("file too large"))
+            } else {
+                hasher.update(&bytes);
+                Ok(bytes)
+            };
+            future::ready(res)
+        });
         let body_reader = StreamReader::new(body_with_io_error);
         futures::pin_mut!(body_reader);
 
         // Create the file. `File` implements `AsyncWrite`.
-        let path = std::path::Path::new(IMAGES_DIR).join(file_name);
-        let mut file = BufWriter::new(File::create(path).await?);
+        let mut file = BufWriter::new(File::create(&staged_file.path).await?);
 
         // Copy the body into the file.
         tokio::io::copy(&mut body_reader, &mut file).await?;
+        file.flush().await
+    }
+    .await;
 
-        Ok::<_, io::Error>(())
+    if byte_size > max_byte_size {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            UploadImageError::FileTooLarge { max_byte_size },
+        ));
     }
-    .await
-    .map_err(|err| {
+    res.map_err(general_error)?;
+
+    Ok((staged_file, content_hash::to_hex(hasher), byte_size))
+}
+
+/// What's known of a staged upload from streaming it.
+struct StagedImage<'a> {
+    path: &'a Path,
+    content_hash: String,
+    byte_size: u64,
+}
+
+/// Validates the staged image against the limits, then reads what's saved of it. Its format is
+/// detected from its content, and its dimensions are checked before it's ever decoded.
+fn read_image_file(
+    file_name: String,
+    staged: StagedImage,
+    limits: &IngestLimits,
+) -> Result<ImageFile, (StatusCode, UploadImageError)> {
+    let StagedImage {
+        path,
+        content_hash,
+        byte_size,
+    } = staged;
+
+    let format = image_formats::sniff_format(path)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                UploadImageError::GeneralError(e.to_string()),
+            )
+        })?
+        .ok_or((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UploadImageError::UnrecognizedFormat,
+        ))?;
+    if !limits.allows(format) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UploadImageError::FormatNotAllowed {
+                format: image_formats::format_name(format).to_string(),
+                allowed_formats: limits.allowed_format_names(),
+            },
+        ));
+    }
+
+    let dimensions = image_dimensions::fetch_image_dimensions_at(path).map_err(|e| {
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            UploadImageError::GeneralError(err.to_string()),
+            StatusCode::BAD_REQUEST,
+            UploadImageError::FailedToFetchDimensions(e),
         )
+    })?;
+    if limits.exceeds_megapixels(dimensions) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            UploadImageError::TooManyPixels {
+                width: dimensions.0,
+                height: dimensions.1,
+                max_megapixels: limits.max_megapixels,
+            },
+        ));
+    }
+
+    let metadata = image_metadata::fetch_image_metadata_at(path);
+    let (width, height) = image_orientation::oriented_dimensions(dimensions, metadata.orientation);
+
+    tracing::debug!("image dimensions: {} x {}", width, height);
+
+    let perceptual_hash =
+        perceptual_hash::fetch_perceptual_hash_at(path, metadata.orientation, limits);
+
+    Ok(ImageFile {
+        image: Image {
+            file_name,
+            width,
+            height,
+        },
+        metadata,
+        content_hash,
+        perceptual_hash,
+        format: Some(image_formats::format_name(format).to_string()),
+        mime_type: Some(format.to_mime_type().to_string()),
+        byte_size,
+        // Moving the file into the images keeps its modification time.
+        modified_at: std::fs::metadata(path)
+            .ok()
+            .and_then(|m| canon::modified_at(&m)),
     })
 }
 
-// to prevent directory traversal attacks we ensure the path consists of exactly one normal
-// component
-fn path_is_valid(path: &str) -> bool {
-    let path = std::path::Path::new(path);
-    let mut components = path.components().peekable();
+/// An upload in [UPLOAD_STAGING_DIR]. It's removed when dropped, unless it was moved into
+/// place, so failed or aborted uploads don't leave files behind.
+pub(super) struct StagedFile {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl StagedFile {
+    /// The file keeps the name of the upload as a suffix, since its format is guessed from the
+    /// extension.
+    async fn new(file_name: &str) -> io::Result<Self> {
+        tokio::fs::create_dir_all(UPLOAD_STAGING_DIR).await?;
+        let path = Path::new(UPLOAD_STAGING_DIR).join(format!(
+            "{:016x}-{}",
+            rand::random::<u64>(),
+            file_name
+        ));
+        Ok(Self {
+            path,
+            persisted: false,
+        })
+    }
+
+    /// Takes over a file staged elsewhere, like a finished resumable upload.
+    pub(super) fn adopt(path: PathBuf) -> Self {
+        Self {
+            path,
+            persisted: false,
+        }
+    }
+
+    /// Moves the file into `dir` as `file_name`.
+    async fn persist(mut self, dir: &str, file_name: &str) -> io::Result<()> {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::rename(&self.path, Path::new(dir).join(file_name)).await?;
+        self.persisted = true;
+        Ok(())
+    }
+}
 
-    if let Some(first) = components.peek() {
-        if !matches!(first, std::path::Component::Normal(_)) {
-            return false;
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if self.persisted {
+            return;
+        }
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                tracing::warn!("failed to remove staged upload {:?}: {}", self.path, e);
+            }
         }
     }
+}
 
-    components.count() == 1
+/// Removes the staged files of uploads interrupted by a shutdown.
+fn clear_staging_dir() {
+    let entries = match std::fs::read_dir(UPLOAD_STAGING_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+        Err(e) => {
+            tracing::warn!("failed to read the upload staging directory: {}", e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        if let Err(e) = std::fs::remove_file(entry.path()) {
+            tracing::warn!("failed to remove staged upload {:?}: {}", entry.path(), e);
+        }
+    }
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-enum FileFieldValidationError {
+pub(super) enum FileFieldValidationError {
     FieldErr(String),
     MissingField,
-    MissingFieldName,
     MissingFileName,
     InvalidFileName,
-    WrongFieldName { expected: String, actual: String },
 }
 
-fn validate_field(
-    field_res: Result<Option<Field>, MultipartError>,
-) -> Result<(String, Field), FileFieldValidationError> {
-    let field = field_res.map_err(|e| FileFieldValidationError::FieldErr(e.to_string()))?;
-    let field = field.ok_or(FileFieldValidationError::MissingField)?;
-    let field_name = field
-        .name()
-        .ok_or(FileFieldValidationError::MissingFieldName)?;
+pub(super) fn validate_field(field: &Field) -> Result<String, FileFieldValidationError> {
     let file_name = field
         .file_name()
         .ok_or(FileFieldValidationError::MissingFileName)?;
@@ -193,13 +757,211 @@ fn validate_field(
         return Err(FileFieldValidationError::InvalidFileName);
     }
 
-    let expected_name = "";
-    if field_name != expected_name {
-        return Err(FileFieldValidationError::WrongFieldName {
-            expected: expected_name.to_string(),
-            actual: field_name.to_string(),
-        });
+    Ok(file_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use axum::{middleware, routing::get};
+    use chrono::NaiveDateTime;
+    use image::{DynamicImage, ImageFormat};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        api::routing::idempotency::{self, IdempotencyKeys},
+        domain::{
+            actions::idempotency::{FetchIdempotentResponse, SaveIdempotentResponse},
+            models::IdempotentResponse,
+        },
+        state::screensaver_manager::ScreensaverManager,
+    };
+
+    /// Saves nothing, only recording the names of the saved images.
+    #[derive(Clone, Default)]
+    struct NewImages {
+        saved: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl DeleteImage for NewImages {
+        async fn delete_image(&self, _: &str) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl FetchImage for NewImages {
+        async fn fetch_image(&self, _: &str) -> Result<Option<Image>, String> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl FetchImageByContentHash for NewImages {
+        async fn fetch_image_by_content_hash(&self, _: &str) -> Result<Option<Image>, String> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl SaveImage for NewImages {
+        async fn save_image(
+            &self,
+            image: &ImageFile,
+            _: &UploadDetails,
+        ) -> Result<(), SaveImageError> {
+            self.saved
+                .lock()
+                .expect("saved should not be poisoned")
+                .push(image.image.file_name.clone());
+            Ok(())
+        }
+    }
+
+    /// Keeps the saved responses in memory, without ever expiring them.
+    #[derive(Clone, Default)]
+    struct SavedResponses {
+        responses: Arc<Mutex<HashMap<(String, String), IdempotentResponse>>>,
     }
 
-    Ok((file_name.to_string(), field))
+    #[async_trait]
+    impl FetchIdempotentResponse for SavedResponses {
+        async fn fetch_idempotent_response(
+            &self,
+            scope: &str,
+            key: &str,
+            _: NaiveDateTime,
+        ) -> Result<Option<IdempotentResponse>, String> {
+            let responses = self
+                .responses
+                .lock()
+                .expect("responses should not be poisoned");
+            Ok(responses
+                .get(&(scope.to_string(), key.to_string()))
+                .cloned())
+        }
+    }
+
+    #[async_trait]
+    impl SaveIdempotentResponse for SavedResponses {
+        async fn save_idempotent_response(
+            &self,
+            scope: &str,
+            key: &str,
+            response: &IdempotentResponse,
+            _: NaiveDateTime,
+        ) -> Result<(), String> {
+            self.responses
+                .lock()
+                .expect("responses should not be poisoned")
+                .insert((scope.to_string(), key.to_string()), response.clone());
+            Ok(())
+        }
+    }
+
+    fn mk_sut(image_mngr: NewImages) -> Router {
+        upload_routes(
+            image_mngr,
+            ScreensaverManager::restore(Vec::new(), HashMap::new()),
+            IngestLimits::default(),
+        )
+        .route("/get", get(|| async {}))
+        .layer(middleware::from_fn(reject_reserved_file_names))
+    }
+
+    async fn put(sut: &Router, file_name: &str, body: Vec<u8>) -> (StatusCode, String) {
+        let request = Request::put(format!("/{}", file_name))
+            .body(Body::from(body))
+            .expect("request should be valid");
+        let res = sut
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router should respond");
+        let status = res.status();
+        let body = hyper::body::to_bytes(res.into_body())
+            .await
+            .expect("body should be readable");
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn put_image_saves_new_image() {
+        // Arrange
+        let image_mngr = NewImages::default();
+        let sut = mk_sut(image_mngr.clone());
+        let file_name = format!("put-test-{:016x}.png", rand::random::<u64>());
+        let body = image_formats::encode(&DynamicImage::new_rgb8(4, 3), ImageFormat::Png)
+            .expect("image should be encodable");
+
+        // Act
+        let (status, _) = put(&sut, &file_name, body).await;
+
+        // Assert
+        let saved_file = Path::new(IMAGES_DIR).join(&file_name);
+        let file_saved = saved_file.exists();
+        let _ = std::fs::remove_file(&saved_file);
+        let _ = image_derivatives::delete_derivatives(&file_name);
+
+        assert_eq!(StatusCode::CREATED, status);
+        assert_eq!(vec![file_name], *image_mngr.saved.lock().unwrap());
+        assert!(file_saved);
+    }
+
+    #[tokio::test]
+    async fn put_image_rejects_names_of_other_routes() {
+        // Arrange
+        let image_mngr = NewImages::default();
+        let sut = mk_sut(image_mngr.clone());
+
+        for file_name in ["get", "upload"] {
+            // Act
+            let (status, body) = put(&sut, file_name, b"not an image".to_vec()).await;
+
+            // Assert
+            assert_eq!(StatusCode::CONFLICT, status);
+            assert_eq!(UploadImageError::ReservedFileName.to_json_string(), body);
+        }
+        assert!(image_mngr.saved.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn replayed_put_image_returns_same_upload_batch() {
+        // Arrange
+        let image_mngr = NewImages::default();
+        let sut = mk_sut(image_mngr.clone()).route_layer(middleware::from_fn_with_state(
+            IdempotencyKeys::from_env(SavedResponses::default())
+                .replaying_headers(&[UPLOAD_BATCH_HEADER]),
+            idempotency::replay_idempotent_response,
+        ));
+        let file_name = format!("put-test-{:016x}.png", rand::random::<u64>());
+        let body = image_formats::encode(&DynamicImage::new_rgb8(4, 3), ImageFormat::Png)
+            .expect("image should be encodable");
+        let put_with_key = || {
+            let request = Request::put(format!("/{}", file_name))
+                .header("idempotency-key", "replayed-put")
+                .body(Body::from(body.clone()))
+                .expect("request should be valid");
+            sut.clone().oneshot(request)
+        };
+        let first = put_with_key().await.expect("router should respond");
+
+        // Act
+        let replayed = put_with_key().await.expect("router should respond");
+
+        // Assert
+        let _ = std::fs::remove_file(Path::new(IMAGES_DIR).join(&file_name));
+        let _ = image_derivatives::delete_derivatives(&file_name);
+
+        assert_eq!(StatusCode::CREATED, first.status());
+        assert_eq!(StatusCode::CREATED, replayed.status());
+        let upload_batch = first.headers().get(UPLOAD_BATCH_HEADER);
+        assert!(upload_batch.is_some());
+        assert_eq!(upload_batch, replayed.headers().get(UPLOAD_BATCH_HEADER));
+        assert_eq!(1, image_mngr.saved.lock().unwrap().len());
+    }
 }
@@ -0,0 +1,161 @@
+use axum::extract::multipart::Field;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::{api::routing::tag::split_tag_names, domain::models::UploadDetails};
+
+/// Details are short, so larger fields are rejected instead of being buffered.
+const MAX_FIELD_LEN: usize = 64 * 1024; /* 64kb */
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UploadDetailsError {
+    FieldErr(String),
+    MissingFieldName,
+    UnknownField(String),
+    #[serde(rename_all = "camelCase")]
+    FieldTooLarge {
+        max_len: usize,
+    },
+    FieldNotUtf8,
+    InvalidTags(String),
+    InvalidTakenAt(String),
+    InvalidExcludedFromScreensaver(String),
+}
+
+/// Reads a multipart field without a file into the details of the files after it.
+///
+/// The fields are `caption`, `album`, `tags` as a JSON array or comma separated names, `takenAt`
+/// as `YYYY-MM-DDTHH:MM:SS`, and `excludedFromScreensaver` as `true` or `false`. An empty value
+/// clears the detail.
+pub async fn read_details_field(
+    mut field: Field<'_>,
+    details: &mut UploadDetails,
+) -> Result<(), UploadDetailsError> {
+    let name = field
+        .name()
+        .ok_or(UploadDetailsError::MissingFieldName)?
+        .to_string();
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| UploadDetailsError::FieldErr(e.to_string()))?
+    {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_FIELD_LEN {
+            return Err(UploadDetailsError::FieldTooLarge {
+                max_len: MAX_FIELD_LEN,
+            });
+        }
+    }
+    let value = String::from_utf8(bytes).map_err(|_| UploadDetailsError::FieldNotUtf8)?;
+
+    apply_field(details, &name, value.trim())
+}
+
+fn apply_field(
+    details: &mut UploadDetails,
+    name: &str,
+    value: &str,
+) -> Result<(), UploadDetailsError> {
+    let non_empty = |v: &str| (!v.is_empty()).then(|| v.to_string());
+    match name {
+        "caption" => details.caption = non_empty(value),
+        "album" => details.album = non_empty(value),
+        "tags" => details.tags = parse_tags(value)?,
+        "takenAt" => {
+            details.taken_at = match value {
+                "" => None,
+                v => Some(
+                    NaiveDateTime::parse_from_str(v, "%Y-%m-%dT%H:%M:%S")
+                        .map_err(|_| UploadDetailsError::InvalidTakenAt(v.to_string()))?,
+                ),
+            }
+        }
+        "excludedFromScreensaver" => {
+            details.excluded_from_screensaver = match value {
+                "" | "false" => false,
+                "true" => true,
+                v => {
+                    return Err(UploadDetailsError::InvalidExcludedFromScreensaver(
+                        v.to_string(),
+                    ))
+                }
+            }
+        }
+        _ => return Err(UploadDetailsError::UnknownField(name.to_string())),
+    }
+
+    Ok(())
+}
+
+/// Parses a JSON array of tag names, or comma separated names. Names are trimmed, and can't be
+/// empty or contain commas, since commas separate names in queries.
+fn parse_tags(value: &str) -> Result<Vec<String>, UploadDetailsError> {
+    if !value.starts_with('[') {
+        return Ok(split_tag_names(value));
+    }
+
+    let names: Vec<String> = serde_json::from_str(value)
+        .map_err(|_| UploadDetailsError::InvalidTags(value.to_string()))?;
+    names
+        .into_iter()
+        .map(|n| {
+            let trimmed = n.trim();
+            if trimmed.is_empty() || trimmed.contains(',') {
+                Err(UploadDetailsError::InvalidTags(n))
+            } else {
+                Ok(trimmed.to_string())
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tags_accepts_json_and_comma_separated_names() {
+        let expected = vec!["beach".to_string(), "summer".to_string()];
+        assert_eq!(
+            expected,
+            parse_tags(r#"["beach", " summer "]"#).ok().unwrap()
+        );
+        assert_eq!(expected, parse_tags("beach, summer,").ok().unwrap());
+    }
+
+    #[test]
+    fn parse_tags_rejects_name_with_comma() {
+        assert!(parse_tags(r#"["beach,summer"]"#).is_err());
+    }
+
+    #[test]
+    fn apply_field_sets_and_clears_details() {
+        // Arrange
+        let mut sut = UploadDetails::default();
+
+        // Act
+        let set = apply_field(&mut sut, "takenAt", "2023-07-04T21:30:00")
+            .and_then(|_| apply_field(&mut sut, "excludedFromScreensaver", "true"))
+            .and_then(|_| apply_field(&mut sut, "caption", "Fireworks"))
+            .and_then(|_| apply_field(&mut sut, "caption", ""));
+
+        // Assert
+        assert!(set.is_ok());
+        assert_eq!(
+            NaiveDateTime::parse_from_str("2023-07-04 21:30:00", "%Y-%m-%d %H:%M:%S").ok(),
+            sut.taken_at
+        );
+        assert!(sut.excluded_from_screensaver);
+        assert_eq!(None, sut.caption);
+    }
+
+    #[test]
+    fn apply_field_rejects_unknown_field() {
+        let mut sut = UploadDetails::default();
+        assert!(apply_field(&mut sut, "rating", "5").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use axum::Router;
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::{
+    api::routing::ApiError,
+    domain::{actions::tag::ImageTagsError, models::Tag},
+    persistence::PersistenceManager,
+};
+
+mod add;
+mod list;
+mod remove;
+
+pub fn make_tag_router(persistence_mngr: &PersistenceManager) -> Router {
+    Router::new().nest(
+        "/tag",
+        Router::new()
+            .merge(add::make_add_router(persistence_mngr.clone()))
+            .merge(remove::make_remove_router(persistence_mngr.clone()))
+            .merge(list::make_list_router(persistence_mngr.clone())),
+    )
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TagResponse {
+    name: String,
+    image_count: u64,
+}
+
+impl From<Tag> for TagResponse {
+    fn from(value: Tag) -> Self {
+        Self {
+            name: value.name,
+            image_count: value.image_count,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ImageTagsApiError {
+    InvalidTagName(String),
+    ImageNotFound,
+    Persistence(String),
+}
+
+impl ApiError for ImageTagsApiError {}
+
+impl From<ImageTagsError> for (StatusCode, String) {
+    fn from(value: ImageTagsError) -> Self {
+        match value {
+            ImageTagsError::ImageNotFound => (
+                StatusCode::NOT_FOUND,
+                ImageTagsApiError::ImageNotFound.to_json_string(),
+            ),
+            ImageTagsError::Persistence(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ImageTagsApiError::Persistence(e).to_json_string(),
+            ),
+        }
+    }
+}
+
+/// Trims the tag names, returning `Err` with the first invalid name.
+/// Names can't be empty or contain commas, since commas separate names in queries.
+fn validate_tag_names(names: Vec<String>) -> Result<Vec<String>, (StatusCode, String)> {
+    names
+        .into_iter()
+        .map(|n| {
+            let trimmed = n.trim();
+            if trimmed.is_empty() || trimmed.contains(',') {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    ImageTagsApiError::InvalidTagName(n).to_json_string(),
+                ))
+            } else {
+                Ok(trimmed.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Splits comma separated tag names, ignoring empty names.
+pub fn split_tag_names(names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,26 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::Deserialize;
+
+use crate::{api::routing::tag::validate_tag_names, domain::actions::tag::AddImageTags};
+
+pub fn make_add_router(ait: impl 'static + Clone + Send + Sync + AddImageTags) -> Router {
+    Router::new().route("/add", post(|body| add_tags(body, ait)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddTagsInput {
+    file_name: String,
+    tags: Vec<String>,
+}
+
+async fn add_tags(
+    Json(input): Json<AddTagsInput>,
+    ait: impl AddImageTags,
+) -> Result<(), (StatusCode, String)> {
+    let tags = validate_tag_names(input.tags)?;
+    ait.add_image_tags(&input.file_name, &tags)
+        .await
+        .map_err(|e| e.into())
+}
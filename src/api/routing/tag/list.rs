@@ -0,0 +1,26 @@
+use axum::{routing::get, Json, Router};
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::{api::routing::tag::TagResponse, domain::actions::tag::FetchTags};
+
+pub fn make_list_router(ft: impl 'static + Clone + Send + Sync + FetchTags) -> Router {
+    Router::new().route("/list", get(|| list_tags(ft)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TagsResponse {
+    tags: Vec<TagResponse>,
+}
+
+async fn list_tags(ft: impl FetchTags) -> Result<Json<TagsResponse>, (StatusCode, String)> {
+    ft.fetch_tags()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map(|v| {
+            Json(TagsResponse {
+                tags: v.into_iter().map(|v| v.into()).collect(),
+            })
+        })
+}
@@ -0,0 +1,26 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::Deserialize;
+
+use crate::domain::actions::tag::RemoveImageTags;
+
+pub fn make_remove_router(rit: impl 'static + Clone + Send + Sync + RemoveImageTags) -> Router {
+    Router::new().route("/remove", post(|body| remove_tags(body, rit)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoveTagsInput {
+    file_name: String,
+    tags: Vec<String>,
+}
+
+async fn remove_tags(
+    Json(input): Json<RemoveTagsInput>,
+    rit: impl RemoveImageTags,
+) -> Result<(), (StatusCode, String)> {
+    let tags: Vec<_> = input.tags.iter().map(|t| t.trim().to_string()).collect();
+    rit.remove_image_tags(&input.file_name, &tags)
+        .await
+        .map_err(|e| e.into())
+}
@@ -0,0 +1,21 @@
+use axum::Router;
+
+use crate::{persistence::PersistenceManager, state::screensaver_manager::ScreensaverManager};
+
+mod delete;
+mod list;
+
+pub fn make_upload_batch_router(
+    persistence_mngr: &PersistenceManager,
+    screensaver_mngr: &ScreensaverManager,
+) -> Router {
+    Router::new().nest(
+        "/upload_batch",
+        Router::new()
+            .merge(list::make_list_router(persistence_mngr.clone()))
+            .merge(delete::make_delete_router(
+                persistence_mngr.clone(),
+                screensaver_mngr.clone(),
+            )),
+    )
+}
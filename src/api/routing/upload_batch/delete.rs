@@ -0,0 +1,105 @@
+use axum::{routing::post, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{delete_image_files, routing::ApiError},
+    domain::{
+        actions::upload_batch::{DeleteUploadBatch, FetchUploadBatchImages},
+        screensaver::Screensaver,
+    },
+};
+
+pub fn make_delete_router(
+    ub_mngr: impl 'static + Clone + Send + Sync + DeleteUploadBatch + FetchUploadBatchImages,
+    screensaver: impl 'static + Clone + Send + Sync + Screensaver,
+) -> Router {
+    Router::new().route(
+        "/delete",
+        post(|body| delete_upload_batch(body, ub_mngr, screensaver)),
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteInput {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteResponse {
+    deleted_file_names: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum DeleteUploadBatchError {
+    BatchNotFound,
+    /// The files that couldn't be removed. The rest of the batch was deleted.
+    Fs(Vec<String>),
+    Persistence(String),
+    Blocking(String),
+}
+
+impl ApiError for DeleteUploadBatchError {}
+
+// Deletes the files of the batch first, then their images and the batch, then removes them from
+// the screensaver. A file that can't be removed keeps its image, since the next canon update
+// would bring it back otherwise.
+async fn delete_upload_batch(
+    Json(input): Json<DeleteInput>,
+    ub_mngr: impl DeleteUploadBatch + FetchUploadBatchImages,
+    mut screensaver: impl Screensaver,
+) -> Result<Json<DeleteResponse>, (StatusCode, String)> {
+    let file_names = ub_mngr
+        .fetch_upload_batch_images(&input.name)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                DeleteUploadBatchError::Persistence(e).to_json_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                DeleteUploadBatchError::BatchNotFound.to_json_string(),
+            )
+        })?;
+
+    let (deleted, failed) = tokio::task::spawn_blocking(move || delete_image_files(file_names))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                DeleteUploadBatchError::Blocking(e.to_string()).to_json_string(),
+            )
+        })?;
+
+    ub_mngr
+        .delete_upload_batch(&input.name, &deleted)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                DeleteUploadBatchError::Persistence(e).to_json_string(),
+            )
+        })?;
+
+    // Images excluded from the screensaver aren't in it.
+    for file_name in &deleted {
+        let _ = screensaver.delete_image(file_name);
+    }
+
+    if !failed.is_empty() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            DeleteUploadBatchError::Fs(failed).to_json_string(),
+        ));
+    }
+
+    Ok(Json(DeleteResponse {
+        deleted_file_names: deleted,
+    }))
+}
@@ -0,0 +1,59 @@
+use axum::{extract::Query, routing::get, Json, Router};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{actions::upload_batch::FetchUploadBatches, models::UploadBatch};
+
+const DEFAULT_LIMIT: u64 = 20;
+const MAX_LIMIT: u64 = 100;
+
+pub fn make_list_router(fub: impl 'static + Clone + Send + Sync + FetchUploadBatches) -> Router {
+    Router::new().route("/list", get(|query| list_upload_batches(query, fub)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListInput {
+    limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadBatchesResponse {
+    batches: Vec<UploadBatchResponse>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadBatchResponse {
+    name: String,
+    /// Formatted as `YYYY-MM-DDTHH:MM:SSZ`, in UTC.
+    created_at: String,
+    image_count: u64,
+}
+
+impl From<UploadBatch> for UploadBatchResponse {
+    fn from(value: UploadBatch) -> Self {
+        Self {
+            name: value.name,
+            created_at: value.created_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            image_count: value.image_count,
+        }
+    }
+}
+
+// Lists the most recent batches first.
+async fn list_upload_batches(
+    Query(input): Query<ListInput>,
+    fub: impl FetchUploadBatches,
+) -> Result<Json<UploadBatchesResponse>, (StatusCode, String)> {
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    fub.fetch_upload_batches(limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map(|v| {
+            Json(UploadBatchesResponse {
+                batches: v.into_iter().map(|v| v.into()).collect(),
+            })
+        })
+}
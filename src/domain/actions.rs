@@ -1 +1,7 @@
+pub mod album;
+pub mod guest_link;
+pub mod idempotency;
 pub mod image;
+pub mod screensaver;
+pub mod tag;
+pub mod upload_batch;
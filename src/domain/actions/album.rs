@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::domain::{
+    actions::image::PaginationOrder,
+    models::{Album, ImagesPage},
+};
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait CreateAlbum {
+    async fn create_album(&self, name: &str) -> Result<(), String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait DeleteAlbum {
+    async fn delete_album(&self, name: &str) -> Result<(), String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchAlbum {
+    async fn fetch_album(&self, name: &str) -> Result<Option<Album>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchAlbums {
+    async fn fetch_albums(&self) -> Result<Vec<Album>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait RenameAlbum {
+    async fn rename_album(&self, old_name: &str, new_name: &str) -> Result<(), String>;
+}
+
+#[derive(Debug)]
+pub enum AlbumImagesError {
+    AlbumNotFound,
+    /// The file names that don't belong to any image.
+    ImagesNotFound(Vec<String>),
+    Persistence(String),
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait AddAlbumImages {
+    /// Adds the images to the album. Images already in the album are ignored.
+    /// If `Err`, no images were added.
+    async fn add_album_images(
+        &self,
+        album_name: &str,
+        file_names: &[String],
+    ) -> Result<(), AlbumImagesError>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait RemoveAlbumImages {
+    /// Removes the images from the album. Images not in the album are ignored.
+    /// If `Err`, no images were removed.
+    async fn remove_album_images(
+        &self,
+        album_name: &str,
+        file_names: &[String],
+    ) -> Result<(), AlbumImagesError>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchAlbumImagesPage {
+    /// Returns `None` if the album doesn't exist.
+    async fn fetch_album_images_page(
+        &self,
+        album_name: &str,
+        count: u64,
+        after: Option<i32>,
+        order: PaginationOrder,
+    ) -> Result<Option<ImagesPage>, String>;
+}
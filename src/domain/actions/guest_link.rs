@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::domain::models::GuestLink;
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait CreateGuestLink {
+    async fn create_guest_link(&self, link: &GuestLink) -> Result<(), String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait DeleteGuestLink {
+    /// Deleting a link keeps the images uploaded through it.
+    async fn delete_guest_link(&self, token: &str) -> Result<(), String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchGuestLink {
+    async fn fetch_guest_link(&self, token: &str) -> Result<Option<GuestLink>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchGuestLinks {
+    /// Fetches the links, the most recently created first.
+    async fn fetch_guest_links(&self) -> Result<Vec<GuestLink>, String>;
+}
+
+#[derive(Debug)]
+pub enum ReserveGuestUploadError {
+    LinkNotFound,
+    LinkExpired,
+    LimitReached,
+    Persistence(String),
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait ReserveGuestUpload {
+    /// Counts an upload of `byte_size` against the link, unless the link expired or the upload
+    /// would exceed its limits. Concurrent uploads can't exceed the limits together.
+    async fn reserve_guest_upload(
+        &self,
+        token: &str,
+        byte_size: u64,
+    ) -> Result<(), ReserveGuestUploadError>;
+
+    /// Gives back an upload that was reserved but failed.
+    async fn release_guest_upload(&self, token: &str, byte_size: u64) -> Result<(), String>;
+}
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+use chrono::NaiveDateTime;
+
+use crate::domain::models::IdempotentResponse;
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchIdempotentResponse {
+    /// Fetches the response saved for the key in the scope, unless it was saved before
+    /// `saved_after`.
+    async fn fetch_idempotent_response(
+        &self,
+        scope: &str,
+        key: &str,
+        saved_after: NaiveDateTime,
+    ) -> Result<Option<IdempotentResponse>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait SaveIdempotentResponse {
+    /// Saves the response for the key in the scope, replacing an expired response for it.
+    /// Responses saved before `expired_before` are removed.
+    async fn save_idempotent_response(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &IdempotentResponse,
+        expired_before: NaiveDateTime,
+    ) -> Result<(), String>;
+}
@@ -1,7 +1,12 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use auto_impl::auto_impl;
 
-use crate::domain::models::{Image, ImagesPage};
+use crate::domain::models::{
+    CanonDiff, Image, ImageFile, ImageFingerprint, ImageMetadata, ImagesPage, PendingImage,
+    TagFilter, UploadDetails,
+};
 
 #[async_trait]
 #[auto_impl(&)]
@@ -18,18 +23,65 @@ pub trait FetchCanon {
 #[async_trait]
 #[auto_impl(&)]
 pub trait UpdateCanon {
-    async fn update_canon<'a, T: Iterator<Item = &'a Image> + Send>(
+    /// Saves the images of the canon, deleting the saved images that aren't in it. The images
+    /// saved under the `kept_file_names` are left as they are. Returns the file names of the
+    /// deleted images.
+    async fn update_canon<'a, T: Iterator<Item = &'a ImageFile> + Send>(
         &self,
         canon: T,
-    ) -> Result<(), String>;
+        kept_file_names: &HashSet<String>,
+    ) -> Result<Vec<String>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait DiffCanon {
+    /// Compares the canon to the saved images like [UpdateCanon::update_canon], without saving
+    /// anything.
+    async fn diff_canon<'a, T: Iterator<Item = &'a ImageFile> + Send>(
+        &self,
+        canon: T,
+        kept_file_names: &HashSet<String>,
+    ) -> Result<CanonDiff, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchImageFingerprints {
+    /// Fetches the fingerprints of the saved images. Images saved without one are left out.
+    async fn fetch_image_fingerprints(&self) -> Result<Vec<ImageFingerprint>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchApprovedImage {
+    /// Fetches the image, unless it's pending approval.
+    async fn fetch_approved_image(&self, file_name: &str) -> Result<Option<Image>, String>;
 }
 
 #[async_trait]
 #[auto_impl(&)]
 pub trait FetchImage {
+    /// Fetches the image, including one pending approval.
     async fn fetch_image(&self, file_name: &str) -> Result<Option<Image>, String>;
 }
 
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchImageByContentHash {
+    async fn fetch_image_by_content_hash(
+        &self,
+        content_hash: &str,
+    ) -> Result<Option<Image>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchPerceptualHashes {
+    /// Returns every image that has a perceptual hash, along with it.
+    async fn fetch_perceptual_hashes(&self) -> Result<Vec<(Image, u64)>, String>;
+}
+
 #[async_trait]
 #[auto_impl(&)]
 pub trait RenameImage {
@@ -44,6 +96,7 @@ pub trait FetchImagesPage {
         count: u64,
         after: Option<i32>,
         order: PaginationOrder,
+        tag_filter: &TagFilter,
     ) -> Result<ImagesPage, String>;
 }
 
@@ -52,8 +105,50 @@ pub enum PaginationOrder {
     OldToNew,
 }
 
+#[derive(Debug)]
+pub enum SaveImageError {
+    AlbumNotFound,
+    GuestLinkNotFound,
+    Persistence(String),
+}
+
 #[async_trait]
 #[auto_impl(&)]
 pub trait SaveImage {
-    async fn save_image(&self, image: &Image) -> Result<(), String>;
+    /// Saves the image along with the details of its upload, all or nothing.
+    /// Images uploaded through a guest link are saved pending approval.
+    async fn save_image(
+        &self,
+        image: &ImageFile,
+        details: &UploadDetails,
+    ) -> Result<(), SaveImageError>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchScreensaverExclusions {
+    /// Returns the file names of the images excluded from the screensaver, including those
+    /// pending approval.
+    async fn fetch_screensaver_exclusions(&self) -> Result<HashSet<String>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchPendingImages {
+    /// Fetches the images pending approval, the oldest first.
+    async fn fetch_pending_images(&self) -> Result<Vec<PendingImage>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait ApproveImages {
+    /// Approves the images pending approval among the given ones. Returns the approved images.
+    async fn approve_images(&self, file_names: &[String]) -> Result<Vec<Image>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchImageMetadata {
+    /// Returns `None` if the image doesn't exist.
+    async fn fetch_image_metadata(&self, file_name: &str) -> Result<Option<ImageMetadata>, String>;
 }
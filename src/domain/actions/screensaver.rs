@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::domain::models::{ScreensaverCursor, ScreensaverRotation};
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchScreensaverRotations {
+    async fn fetch_screensaver_rotations(&self) -> Result<Vec<ScreensaverRotation>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait SaveScreensaverRotations {
+    /// Saves the given rotations, replacing any saved rotation for the same device.
+    async fn save_screensaver_rotations(
+        &self,
+        rotations: &[ScreensaverRotation],
+    ) -> Result<(), String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait SaveScreensaverCursors {
+    /// Saves the current index of the given rotations, leaving their saved order as it is.
+    async fn save_screensaver_cursors(&self, cursors: &[ScreensaverCursor]) -> Result<(), String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait DeleteScreensaverRotations {
+    /// Deletes the saved rotations of the given devices.
+    async fn delete_screensaver_rotations(&self, device_ids: &[String]) -> Result<(), String>;
+}
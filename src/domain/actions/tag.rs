@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::domain::models::Tag;
+
+#[derive(Debug)]
+pub enum ImageTagsError {
+    ImageNotFound,
+    Persistence(String),
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait AddImageTags {
+    /// Adds the tags to the image, creating any tags that don't exist yet.
+    /// Tags the image already has are ignored.
+    async fn add_image_tags(&self, file_name: &str, tags: &[String]) -> Result<(), ImageTagsError>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait RemoveImageTags {
+    /// Removes the tags from the image. Tags the image doesn't have are ignored.
+    async fn remove_image_tags(
+        &self,
+        file_name: &str,
+        tags: &[String],
+    ) -> Result<(), ImageTagsError>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchTags {
+    /// Fetches every tag that at least one image has.
+    async fn fetch_tags(&self) -> Result<Vec<Tag>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchImagesTags {
+    /// Fetches the tags of each of the given images, keyed by file name.
+    /// Images without tags are not included.
+    async fn fetch_images_tags(
+        &self,
+        file_names: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, String>;
+}
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::domain::models::UploadBatch;
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchUploadBatches {
+    /// Fetches up to `limit` batches, the most recently created first.
+    async fn fetch_upload_batches(&self, limit: u64) -> Result<Vec<UploadBatch>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait FetchUploadBatchImages {
+    /// Fetches the file names of the images in the batch.
+    /// Returns `None` if the batch doesn't exist.
+    async fn fetch_upload_batch_images(&self, name: &str) -> Result<Option<Vec<String>>, String>;
+}
+
+#[async_trait]
+#[auto_impl(&)]
+pub trait DeleteUploadBatch {
+    /// Deletes the given images of the batch, then the batch itself unless images are left in it.
+    async fn delete_upload_batch(&self, name: &str, file_names: &[String]) -> Result<(), String>;
+}
@@ -1,3 +1,5 @@
+use chrono::NaiveDateTime;
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Image {
     pub file_name: String,
@@ -5,7 +7,171 @@ pub struct Image {
     pub height: u32,
 }
 
+/// An image along with the data read from its file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImageFile {
+    pub image: Image,
+    pub metadata: ImageMetadata,
+    /// The hex encoded SHA-256 of the file.
+    pub content_hash: String,
+    /// `None` when the image couldn't be decoded.
+    pub perceptual_hash: Option<u64>,
+    /// The format detected from the content of the file, like `png`. `None` when it isn't
+    /// recognized.
+    pub format: Option<String>,
+    pub mime_type: Option<String>,
+    pub byte_size: u64,
+    /// When the file was last modified, to the microsecond. `None` when it's unknown, in which
+    /// case the next canon update reads the file again.
+    pub modified_at: Option<NaiveDateTime>,
+}
+
+/// A saved image along with the size and modification time of its file when it was last read.
+/// Files that still match it don't have to be read again.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ImageFingerprint {
+    pub image: Image,
+    pub byte_size: u64,
+    pub modified_at: NaiveDateTime,
+}
+
+/// What updating the canon would change in the saved images.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CanonDiff {
+    /// The file names of the images that would be saved.
+    pub inserts: Vec<String>,
+    /// The file names of the saved images that would be deleted.
+    pub deletes: Vec<String>,
+    pub dimension_changes: Vec<DimensionChange>,
+}
+
+/// The dimensions of a saved image before and after updating the canon.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DimensionChange {
+    pub file_name: String,
+    pub saved: (u32, u32),
+    pub current: (u32, u32),
+}
+
+/// Metadata read from the EXIF of an image. Every field is `None` when the image has no EXIF.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ImageMetadata {
+    pub taken_at: Option<NaiveDateTime>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    /// In seconds.
+    pub exposure_time: Option<f64>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    /// In millimeters.
+    pub focal_length: Option<f64>,
+    /// The EXIF orientation, from 1 to 8.
+    pub orientation: Option<u16>,
+    /// In decimal degrees, negative in the southern hemisphere.
+    pub gps_latitude: Option<f64>,
+    /// In decimal degrees, negative in the western hemisphere.
+    pub gps_longitude: Option<f64>,
+}
+
+/// Details given along with an upload, saved with the image.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct UploadDetails {
+    pub caption: Option<String>,
+    /// Tags that don't exist yet are created.
+    pub tags: Vec<String>,
+    /// The name of an existing album to add the image to.
+    pub album: Option<String>,
+    /// Replaces the time read from the EXIF, and is kept when the EXIF changes.
+    pub taken_at: Option<NaiveDateTime>,
+    pub excluded_from_screensaver: bool,
+    /// The name of the batch the upload belongs to. It's created with the first image in it.
+    pub upload_batch: Option<String>,
+    /// The token of the guest link the image was uploaded through. Such images are pending
+    /// approval until they're moderated.
+    pub guest_link: Option<String>,
+}
+
 pub struct ImagesPage {
     pub images: Vec<Image>,
     pub cursor: Option<i32>,
 }
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Album {
+    pub name: String,
+    pub image_count: u64,
+}
+
+/// A snapshot of a screensaver rotation, used to restore it later.
+pub struct ScreensaverRotation {
+    /// The device the rotation belongs to, `None` for the default rotation.
+    pub device_id: Option<String>,
+    /// The file names of the images in the order they are shown.
+    pub file_names: Vec<String>,
+    /// The index into `file_names` of the current image.
+    pub current_index: Option<usize>,
+}
+
+/// The position of a screensaver rotation whose order didn't change since it was last saved.
+pub struct ScreensaverCursor {
+    /// The device the rotation belongs to, `None` for the default rotation.
+    pub device_id: Option<String>,
+    /// The index into the saved file names of the current image.
+    pub current_index: Option<usize>,
+}
+
+/// The images uploaded together, like the images imported from a memory card.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UploadBatch {
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub image_count: u64,
+}
+
+/// A link guests upload images through, without access to the rest of the API.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GuestLink {
+    pub token: String,
+    pub label: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub max_uploads: u32,
+    /// Limits the total size of the uploads through the link.
+    pub max_byte_size: u64,
+    pub upload_count: u32,
+    pub uploaded_byte_size: u64,
+}
+
+/// An image uploaded by a guest that waits for approval.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PendingImage {
+    pub image: Image,
+    /// `None` when the link was deleted since.
+    pub guest_link_label: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub image_count: u64,
+}
+
+/// Restricts images to those with matching tags. Empty lists don't restrict anything.
+#[derive(Default)]
+pub struct TagFilter {
+    /// Images must have at least one of these tags.
+    pub any_of: Vec<String>,
+    /// Images must have all of these tags.
+    pub all_of: Vec<String>,
+}
+
+/// A response saved for an idempotency key, replayed when the request is retried with the key.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IdempotentResponse {
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub body: String,
+    /// The names and values of the other headers replayed with the response.
+    pub headers: Vec<(String, String)>,
+}
@@ -53,4 +53,25 @@ pub trait Screensaver {
     /// Shuffles the given `Image`s and replaces the images in the internal structure.
     /// The key should be the file name of the image the key refers to.
     fn replace(&mut self, values: HashMap<String, Image>);
+
+    /// Replaces the images in the internal structure with the given `Image`s without shuffling.
+    /// Images not in `values` are removed, contained images are updated in place,
+    /// and new images are inserted into random locations.
+    /// The key should be the file name of the image the key refers to.
+    fn merge(&mut self, values: HashMap<String, Image>);
+}
+
+/// Provides an independent [Screensaver] rotation for each device.
+/// Every rotation holds the same images, but has its own order and current image.
+/// A `device_id` of `None` refers to the default rotation.
+pub trait DeviceScreensaver {
+    /// Returns the current image of the device's rotation if it exists.
+    /// A device without a rotation gets the current image of the default rotation.
+    fn current_for_device(&self, device_id: Option<&str>) -> Option<Image>;
+
+    /// Resolves an image of the given name in the device's rotation.
+    /// A device without a rotation gets one, starting from the current image of the
+    /// default rotation, when it resolves that image.
+    /// Implementors may drop the rotations of devices that haven't resolved an image in a while.
+    fn resolve_for_device(&mut self, device_id: Option<&str>, file_name: &str) -> ResolveState;
 }
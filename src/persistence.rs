@@ -5,9 +5,15 @@ use sea_orm_migration::MigratorTrait;
 
 use crate::persistence::migrator::Migrator;
 
+pub mod album;
 mod entities;
+pub mod guest_link;
+pub mod idempotency;
 pub mod image;
 mod migrator;
+pub mod screensaver;
+pub mod tag;
+pub mod upload_batch;
 
 pub async fn init_persistence() -> PersistenceManager {
     let db_conn = connect().await;
@@ -0,0 +1,90 @@
+use sea_orm::{
+    sea_query::{Expr, IntoCondition},
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, JoinType, QueryFilter,
+    QuerySelect, RelationTrait, Select,
+};
+
+use crate::{
+    domain::models::Album,
+    persistence::entities::{album_images, albums, images, prelude::Albums, prelude::Images},
+};
+
+pub mod add_album_images;
+pub mod create_album;
+pub mod delete_album;
+pub mod fetch_album;
+pub mod fetch_album_images_page;
+pub mod fetch_albums;
+pub mod remove_album_images;
+pub mod rename_album;
+
+#[derive(FromQueryResult)]
+struct AlbumWithCount {
+    name: String,
+    image_count: i64,
+}
+
+impl From<AlbumWithCount> for Album {
+    fn from(value: AlbumWithCount) -> Self {
+        Self {
+            name: value.name,
+            image_count: value.image_count as u64,
+        }
+    }
+}
+
+/// Selects albums along with the number of images in each. Images pending approval aren't
+/// counted.
+fn select_albums_with_count() -> Select<Albums> {
+    Albums::find()
+        .select_only()
+        .column(albums::Column::Name)
+        .column_as(images::Column::Id.count(), "image_count")
+        .join(JoinType::LeftJoin, albums::Relation::AlbumImages.def())
+        .join(
+            JoinType::LeftJoin,
+            album_images::Relation::Images
+                .def()
+                .on_condition(|_, images| {
+                    Expr::col((images, images::Column::PendingApproval))
+                        .eq(false)
+                        .into_condition()
+                }),
+        )
+        .group_by(albums::Column::Id)
+}
+
+pub(super) async fn fetch_album_model(
+    db: &impl ConnectionTrait,
+    name: &str,
+) -> Result<Option<albums::Model>, DbErr> {
+    Albums::find()
+        .filter(albums::Column::Name.eq(name))
+        .one(db)
+        .await
+}
+
+/// Fetches the images with the given file names.
+/// Returns `Err` with the file names that don't belong to any image, counting those of images
+/// pending approval, which can't be in albums yet.
+async fn fetch_image_models(
+    db: &impl ConnectionTrait,
+    file_names: &[String],
+) -> Result<Result<Vec<images::Model>, Vec<String>>, DbErr> {
+    let models = Images::find()
+        .filter(images::Column::FileName.is_in(file_names.iter().cloned()))
+        .filter(images::Column::PendingApproval.eq(false))
+        .all(db)
+        .await?;
+
+    let missing: Vec<_> = file_names
+        .iter()
+        .filter(|n| !models.iter().any(|m| &m.file_name == *n))
+        .cloned()
+        .collect();
+    if missing.is_empty() {
+        Ok(Ok(models))
+    } else {
+        Ok(Err(missing))
+    }
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use sea_orm::{sea_query::OnConflict, ActiveValue, EntityTrait};
+
+use crate::{
+    domain::actions::album::{AddAlbumImages, AlbumImagesError},
+    persistence::{
+        album::{fetch_album_model, fetch_image_models},
+        entities::{album_images, prelude::AlbumImages},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl AddAlbumImages for PersistenceManager {
+    async fn add_album_images(
+        &self,
+        album_name: &str,
+        file_names: &[String],
+    ) -> Result<(), AlbumImagesError> {
+        let album = fetch_album_model(&self.db_conn, album_name)
+            .await
+            .map_err(|e| AlbumImagesError::Persistence(e.to_string()))?
+            .ok_or(AlbumImagesError::AlbumNotFound)?;
+        let images = fetch_image_models(&self.db_conn, file_names)
+            .await
+            .map_err(|e| AlbumImagesError::Persistence(e.to_string()))?
+            .map_err(AlbumImagesError::ImagesNotFound)?;
+        if images.is_empty() {
+            return Ok(());
+        }
+
+        let models = images.into_iter().map(|i| album_images::ActiveModel {
+            album_id: ActiveValue::Set(album.id),
+            image_id: ActiveValue::Set(i.id),
+        });
+        AlbumImages::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([album_images::Column::AlbumId, album_images::Column::ImageId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| AlbumImagesError::Persistence(e.to_string()))?;
+
+        Ok(())
+    }
+}
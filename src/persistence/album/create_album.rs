@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use sea_orm::{ActiveValue, EntityTrait};
+
+use crate::{
+    domain::actions::album::CreateAlbum,
+    persistence::{
+        entities::{albums, prelude::Albums},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl CreateAlbum for PersistenceManager {
+    async fn create_album(&self, name: &str) -> Result<(), String> {
+        let model = albums::ActiveModel {
+            name: ActiveValue::Set(name.to_string()),
+            ..Default::default()
+        };
+        Albums::insert(model)
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
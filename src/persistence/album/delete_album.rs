@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::album::DeleteAlbum,
+    persistence::{
+        entities::{albums, prelude::Albums},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl DeleteAlbum for PersistenceManager {
+    async fn delete_album(&self, name: &str) -> Result<(), String> {
+        Albums::delete_many()
+            .filter(albums::Column::Name.eq(name))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
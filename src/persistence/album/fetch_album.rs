@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::album::FetchAlbum, models::Album},
+    persistence::{
+        album::{select_albums_with_count, AlbumWithCount},
+        entities::albums,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchAlbum for PersistenceManager {
+    async fn fetch_album(&self, name: &str) -> Result<Option<Album>, String> {
+        Ok(select_albums_with_count()
+            .filter(albums::Column::Name.eq(name))
+            .into_model::<AlbumWithCount>()
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|m| m.into()))
+    }
+}
@@ -0,0 +1,41 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{
+        actions::{album::FetchAlbumImagesPage, image::PaginationOrder},
+        models::ImagesPage,
+    },
+    persistence::{
+        album::fetch_album_model,
+        entities::{album_images, images, prelude::AlbumImages, prelude::Images},
+        image::fetch_images_page::fetch_page,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchAlbumImagesPage for PersistenceManager {
+    async fn fetch_album_images_page(
+        &self,
+        album_name: &str,
+        count: u64,
+        cursor_value: Option<i32>,
+        order: PaginationOrder,
+    ) -> Result<Option<ImagesPage>, String> {
+        let album = fetch_album_model(&self.db_conn, album_name)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(album) = album else {
+            return Ok(None);
+        };
+
+        let select = Images::find()
+            .inner_join(AlbumImages)
+            .filter(album_images::Column::AlbumId.eq(album.id))
+            .filter(images::Column::PendingApproval.eq(false));
+        let page = fetch_page(select, &self.db_conn, count, cursor_value, order).await?;
+
+        Ok(Some(page))
+    }
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use sea_orm::QueryOrder;
+
+use crate::{
+    domain::{actions::album::FetchAlbums, models::Album},
+    persistence::{
+        album::{select_albums_with_count, AlbumWithCount},
+        entities::albums,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchAlbums for PersistenceManager {
+    async fn fetch_albums(&self) -> Result<Vec<Album>, String> {
+        let albums: Vec<_> = select_albums_with_count()
+            .order_by_asc(albums::Column::Name)
+            .into_model::<AlbumWithCount>()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|m| m.into())
+            .collect();
+
+        Ok(albums)
+    }
+}
@@ -0,0 +1,41 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::album::{AlbumImagesError, RemoveAlbumImages},
+    persistence::{
+        album::{fetch_album_model, fetch_image_models},
+        entities::{album_images, prelude::AlbumImages},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl RemoveAlbumImages for PersistenceManager {
+    async fn remove_album_images(
+        &self,
+        album_name: &str,
+        file_names: &[String],
+    ) -> Result<(), AlbumImagesError> {
+        let album = fetch_album_model(&self.db_conn, album_name)
+            .await
+            .map_err(|e| AlbumImagesError::Persistence(e.to_string()))?
+            .ok_or(AlbumImagesError::AlbumNotFound)?;
+        let image_ids: Vec<_> = fetch_image_models(&self.db_conn, file_names)
+            .await
+            .map_err(|e| AlbumImagesError::Persistence(e.to_string()))?
+            .map_err(AlbumImagesError::ImagesNotFound)?
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+
+        AlbumImages::delete_many()
+            .filter(album_images::Column::AlbumId.eq(album.id))
+            .filter(album_images::Column::ImageId.is_in(image_ids))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| AlbumImagesError::Persistence(e.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::album::RenameAlbum,
+    persistence::{
+        entities::{albums, prelude::Albums},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl RenameAlbum for PersistenceManager {
+    async fn rename_album(&self, old_name: &str, new_name: &str) -> Result<(), String> {
+        Albums::update_many()
+            .col_expr(albums::Column::Name, Expr::value(new_name))
+            .filter(albums::Column::Name.eq(old_name))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
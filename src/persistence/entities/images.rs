@@ -11,9 +11,93 @@ pub struct Model {
     pub file_name: String,
     pub width: i32,
     pub height: i32,
+    #[sea_orm(unique)]
+    pub content_hash: Option<String>,
+    pub perceptual_hash: Option<i64>,
+    pub format: Option<String>,
+    pub mime_type: Option<String>,
+    pub byte_size: Option<i64>,
+    pub file_modified_at: Option<DateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub caption: Option<String>,
+    pub excluded_from_screensaver: bool,
+    pub upload_batch_id: Option<i32>,
+    pub pending_approval: bool,
+    pub guest_link_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::album_images::Entity")]
+    AlbumImages,
+    #[sea_orm(
+        belongs_to = "super::guest_links::Entity",
+        from = "Column::GuestLinkId",
+        to = "super::guest_links::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    GuestLinks,
+    #[sea_orm(has_one = "super::image_metadata::Entity")]
+    ImageMetadata,
+    #[sea_orm(has_many = "super::image_tags::Entity")]
+    ImageTags,
+    #[sea_orm(
+        belongs_to = "super::upload_batches::Entity",
+        from = "Column::UploadBatchId",
+        to = "super::upload_batches::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    UploadBatches,
+}
+
+impl Related<super::album_images::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AlbumImages.def()
+    }
+}
+
+impl Related<super::albums::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::album_images::Relation::Albums.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::album_images::Relation::Images.def().rev())
+    }
+}
+
+impl Related<super::guest_links::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GuestLinks.def()
+    }
+}
+
+impl Related<super::image_metadata::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ImageMetadata.def()
+    }
+}
+
+impl Related<super::image_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ImageTags.def()
+    }
+}
+
+impl Related<super::tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::image_tags::Relation::Tags.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::image_tags::Relation::Images.def().rev())
+    }
+}
+
+impl Related<super::upload_batches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UploadBatches.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
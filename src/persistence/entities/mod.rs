@@ -2,4 +2,13 @@
 
 pub mod prelude;
 
+pub mod album_images;
+pub mod albums;
+pub mod guest_links;
+pub mod idempotent_responses;
+pub mod image_metadata;
+pub mod image_tags;
 pub mod images;
+pub mod screensaver_rotations;
+pub mod tags;
+pub mod upload_batches;
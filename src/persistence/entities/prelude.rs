@@ -1,3 +1,12 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+pub use super::album_images::Entity as AlbumImages;
+pub use super::albums::Entity as Albums;
+pub use super::guest_links::Entity as GuestLinks;
+pub use super::idempotent_responses::Entity as IdempotentResponses;
+pub use super::image_metadata::Entity as ImageMetadata;
+pub use super::image_tags::Entity as ImageTags;
 pub use super::images::Entity as Images;
+pub use super::screensaver_rotations::Entity as ScreensaverRotations;
+pub use super::tags::Entity as Tags;
+pub use super::upload_batches::Entity as UploadBatches;
@@ -0,0 +1,38 @@
+use sea_orm::ActiveValue;
+
+use crate::{domain::models::GuestLink, persistence::entities::guest_links};
+
+pub mod create_guest_link;
+pub mod delete_guest_link;
+pub mod fetch_guest_link;
+pub mod fetch_guest_links;
+pub mod reserve_guest_upload;
+
+fn active_model_for_insert_from(link: &GuestLink) -> guest_links::ActiveModel {
+    guest_links::ActiveModel {
+        token: ActiveValue::Set(link.token.clone()),
+        label: ActiveValue::Set(link.label.clone()),
+        created_at: ActiveValue::Set(link.created_at),
+        expires_at: ActiveValue::Set(link.expires_at),
+        max_uploads: ActiveValue::Set(link.max_uploads as i32),
+        max_byte_size: ActiveValue::Set(link.max_byte_size as i64),
+        upload_count: ActiveValue::Set(link.upload_count as i32),
+        uploaded_byte_size: ActiveValue::Set(link.uploaded_byte_size as i64),
+        ..Default::default()
+    }
+}
+
+impl From<guest_links::Model> for GuestLink {
+    fn from(value: guest_links::Model) -> Self {
+        Self {
+            token: value.token,
+            label: value.label,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            max_uploads: value.max_uploads as u32,
+            max_byte_size: value.max_byte_size as u64,
+            upload_count: value.upload_count as u32,
+            uploaded_byte_size: value.uploaded_byte_size as u64,
+        }
+    }
+}
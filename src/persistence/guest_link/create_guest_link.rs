@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use sea_orm::EntityTrait;
+
+use crate::{
+    domain::{actions::guest_link::CreateGuestLink, models::GuestLink},
+    persistence::{
+        entities::prelude::GuestLinks, guest_link::active_model_for_insert_from, PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl CreateGuestLink for PersistenceManager {
+    async fn create_guest_link(&self, link: &GuestLink) -> Result<(), String> {
+        GuestLinks::insert(active_model_for_insert_from(link))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::guest_link::DeleteGuestLink,
+    persistence::{
+        entities::{guest_links, prelude::GuestLinks},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl DeleteGuestLink for PersistenceManager {
+    async fn delete_guest_link(&self, token: &str) -> Result<(), String> {
+        GuestLinks::delete_many()
+            .filter(guest_links::Column::Token.eq(token))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
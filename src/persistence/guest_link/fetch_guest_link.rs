@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::guest_link::FetchGuestLink, models::GuestLink},
+    persistence::{
+        entities::{guest_links, prelude::GuestLinks},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchGuestLink for PersistenceManager {
+    async fn fetch_guest_link(&self, token: &str) -> Result<Option<GuestLink>, String> {
+        Ok(GuestLinks::find()
+            .filter(guest_links::Column::Token.eq(token))
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|m| m.into()))
+    }
+}
@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use sea_orm::{EntityTrait, QueryOrder};
+
+use crate::{
+    domain::{actions::guest_link::FetchGuestLinks, models::GuestLink},
+    persistence::{
+        entities::{guest_links, prelude::GuestLinks},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchGuestLinks for PersistenceManager {
+    async fn fetch_guest_links(&self) -> Result<Vec<GuestLink>, String> {
+        Ok(GuestLinks::find()
+            .order_by_desc(guest_links::Column::CreatedAt)
+            .order_by_desc(guest_links::Column::Id)
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|m| m.into())
+            .collect())
+    }
+}
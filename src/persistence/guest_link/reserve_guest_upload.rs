@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::guest_link::{FetchGuestLink, ReserveGuestUpload, ReserveGuestUploadError},
+    persistence::{
+        entities::{guest_links, prelude::GuestLinks},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl ReserveGuestUpload for PersistenceManager {
+    async fn reserve_guest_upload(
+        &self,
+        token: &str,
+        byte_size: u64,
+    ) -> Result<(), ReserveGuestUploadError> {
+        let now = Utc::now().naive_utc();
+        // Checked and counted in one statement, so concurrent uploads can't both take the last
+        // of the limits.
+        let res = GuestLinks::update_many()
+            .col_expr(
+                guest_links::Column::UploadCount,
+                Expr::col(guest_links::Column::UploadCount).add(1),
+            )
+            .col_expr(
+                guest_links::Column::UploadedByteSize,
+                Expr::col(guest_links::Column::UploadedByteSize).add(byte_size as i64),
+            )
+            .filter(guest_links::Column::Token.eq(token))
+            .filter(guest_links::Column::ExpiresAt.gt(now))
+            .filter(
+                Expr::col(guest_links::Column::UploadCount)
+                    .lt(Expr::col(guest_links::Column::MaxUploads)),
+            )
+            .filter(
+                Expr::expr(Expr::col(guest_links::Column::UploadedByteSize).add(byte_size as i64))
+                    .lte(Expr::col(guest_links::Column::MaxByteSize)),
+            )
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| ReserveGuestUploadError::Persistence(e.to_string()))?;
+        if res.rows_affected > 0 {
+            return Ok(());
+        }
+
+        let link = self
+            .fetch_guest_link(token)
+            .await
+            .map_err(ReserveGuestUploadError::Persistence)?;
+        match link {
+            None => Err(ReserveGuestUploadError::LinkNotFound),
+            Some(link) if link.expires_at <= now => Err(ReserveGuestUploadError::LinkExpired),
+            Some(_) => Err(ReserveGuestUploadError::LimitReached),
+        }
+    }
+
+    async fn release_guest_upload(&self, token: &str, byte_size: u64) -> Result<(), String> {
+        GuestLinks::update_many()
+            .col_expr(
+                guest_links::Column::UploadCount,
+                Expr::col(guest_links::Column::UploadCount).sub(1),
+            )
+            .col_expr(
+                guest_links::Column::UploadedByteSize,
+                Expr::col(guest_links::Column::UploadedByteSize).sub(byte_size as i64),
+            )
+            .filter(guest_links::Column::Token.eq(token))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+use crate::{domain::models::IdempotentResponse, persistence::entities::idempotent_responses};
+
+pub mod fetch_idempotent_response;
+pub mod save_idempotent_response;
+
+impl TryFrom<idempotent_responses::Model> for IdempotentResponse {
+    type Error = String;
+
+    fn try_from(value: idempotent_responses::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            status_code: value.status_code as u16,
+            content_type: value.content_type,
+            body: value.body,
+            headers: serde_json::from_value(value.headers).map_err(|e| e.to_string())?,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::idempotency::FetchIdempotentResponse, models::IdempotentResponse},
+    persistence::{
+        entities::{idempotent_responses, prelude::IdempotentResponses},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchIdempotentResponse for PersistenceManager {
+    async fn fetch_idempotent_response(
+        &self,
+        scope: &str,
+        key: &str,
+        saved_after: NaiveDateTime,
+    ) -> Result<Option<IdempotentResponse>, String> {
+        IdempotentResponses::find()
+            .filter(idempotent_responses::Column::Scope.eq(scope))
+            .filter(idempotent_responses::Column::Key.eq(key))
+            .filter(idempotent_responses::Column::SavedAt.gt(saved_after))
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|m| m.try_into())
+            .transpose()
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{sea_query::OnConflict, ActiveValue, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::idempotency::SaveIdempotentResponse, models::IdempotentResponse},
+    persistence::{
+        entities::{idempotent_responses, prelude::IdempotentResponses},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl SaveIdempotentResponse for PersistenceManager {
+    async fn save_idempotent_response(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &IdempotentResponse,
+        expired_before: NaiveDateTime,
+    ) -> Result<(), String> {
+        IdempotentResponses::delete_many()
+            .filter(idempotent_responses::Column::SavedAt.lt(expired_before))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let model = idempotent_responses::ActiveModel {
+            scope: ActiveValue::Set(scope.to_string()),
+            key: ActiveValue::Set(key.to_string()),
+            status_code: ActiveValue::Set(response.status_code as i16),
+            content_type: ActiveValue::Set(response.content_type.clone()),
+            body: ActiveValue::Set(response.body.clone()),
+            headers: ActiveValue::Set(
+                serde_json::to_value(&response.headers).map_err(|e| e.to_string())?,
+            ),
+            saved_at: ActiveValue::Set(Utc::now().naive_utc()),
+        };
+        // An expired response that wasn't removed yet is replaced.
+        let on_conflict = OnConflict::columns([
+            idempotent_responses::Column::Scope,
+            idempotent_responses::Column::Key,
+        ])
+        .update_columns([
+            idempotent_responses::Column::StatusCode,
+            idempotent_responses::Column::ContentType,
+            idempotent_responses::Column::Body,
+            idempotent_responses::Column::Headers,
+            idempotent_responses::Column::SavedAt,
+        ])
+        .to_owned();
+        IdempotentResponses::insert(model)
+            .on_conflict(on_conflict)
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
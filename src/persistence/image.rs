@@ -1,20 +1,39 @@
 use sea_orm::ActiveValue;
 
-use crate::{domain::models::Image, persistence::entities::images};
+use crate::{
+    domain::models::{Image, ImageFile, ImageMetadata},
+    persistence::entities::{image_metadata, images},
+};
 
+pub mod approve_images;
 pub mod delete_image;
+pub mod diff_canon;
+pub mod fetch_approved_image;
 pub mod fetch_canon;
 pub mod fetch_image;
+pub mod fetch_image_by_content_hash;
+pub mod fetch_image_fingerprints;
+pub mod fetch_image_metadata;
 pub mod fetch_images_page;
+pub mod fetch_pending_images;
+pub mod fetch_perceptual_hashes;
+pub mod fetch_screensaver_exclusions;
 pub mod rename_image;
 pub mod save_image;
 pub mod update_canon;
 
-fn active_model_for_insert_from(image: &Image) -> images::ActiveModel {
+fn active_model_for_insert_from(image_file: &ImageFile) -> images::ActiveModel {
+    let image = &image_file.image;
     images::ActiveModel {
         file_name: ActiveValue::Set(image.file_name.clone()),
         width: ActiveValue::Set(image.width as i32),
         height: ActiveValue::Set(image.height as i32),
+        content_hash: ActiveValue::Set(Some(image_file.content_hash.clone())),
+        perceptual_hash: ActiveValue::Set(image_file.perceptual_hash.map(|v| v as i64)),
+        format: ActiveValue::Set(image_file.format.clone()),
+        mime_type: ActiveValue::Set(image_file.mime_type.clone()),
+        byte_size: ActiveValue::Set(Some(image_file.byte_size as i64)),
+        file_modified_at: ActiveValue::Set(image_file.modified_at),
         ..Default::default()
     }
 }
@@ -28,3 +47,42 @@ impl From<images::Model> for Image {
         }
     }
 }
+
+fn metadata_active_model_from(
+    image_id: i32,
+    metadata: &ImageMetadata,
+) -> image_metadata::ActiveModel {
+    image_metadata::ActiveModel {
+        image_id: ActiveValue::Set(image_id),
+        taken_at: ActiveValue::Set(metadata.taken_at),
+        camera_make: ActiveValue::Set(metadata.camera_make.clone()),
+        camera_model: ActiveValue::Set(metadata.camera_model.clone()),
+        lens_model: ActiveValue::Set(metadata.lens_model.clone()),
+        exposure_time: ActiveValue::Set(metadata.exposure_time),
+        f_number: ActiveValue::Set(metadata.f_number),
+        iso: ActiveValue::Set(metadata.iso.map(|v| v as i32)),
+        focal_length: ActiveValue::Set(metadata.focal_length),
+        orientation: ActiveValue::Set(metadata.orientation.map(|v| v as i16)),
+        gps_latitude: ActiveValue::Set(metadata.gps_latitude),
+        gps_longitude: ActiveValue::Set(metadata.gps_longitude),
+        ..Default::default()
+    }
+}
+
+impl From<image_metadata::Model> for ImageMetadata {
+    fn from(value: image_metadata::Model) -> Self {
+        Self {
+            taken_at: value.taken_at,
+            camera_make: value.camera_make,
+            camera_model: value.camera_model,
+            lens_model: value.lens_model,
+            exposure_time: value.exposure_time,
+            f_number: value.f_number,
+            iso: value.iso.map(|v| v as u32),
+            focal_length: value.focal_length,
+            orientation: value.orientation.map(|v| v as u16),
+            gps_latitude: value.gps_latitude,
+            gps_longitude: value.gps_longitude,
+        }
+    }
+}
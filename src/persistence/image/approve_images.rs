@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use sea_orm::{sea_query::Expr, ColumnTrait, DbErr, EntityTrait, QueryFilter, TransactionTrait};
+
+use crate::{
+    domain::{actions::image::ApproveImages, models::Image},
+    persistence::{
+        entities::{images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl ApproveImages for PersistenceManager {
+    async fn approve_images(&self, file_names: &[String]) -> Result<Vec<Image>, String> {
+        let file_names = file_names.to_vec();
+        let approved = self
+            .db_conn
+            .transaction::<_, Vec<images::Model>, DbErr>(|txn| {
+                Box::pin(async move {
+                    let models = Images::find()
+                        .filter(images::Column::FileName.is_in(file_names))
+                        .filter(images::Column::PendingApproval.eq(true))
+                        .all(txn)
+                        .await?;
+                    Images::update_many()
+                        .col_expr(images::Column::PendingApproval, Expr::value(false))
+                        .filter(images::Column::Id.is_in(models.iter().map(|m| m.id)))
+                        .exec(txn)
+                        .await?;
+
+                    Ok(models)
+                })
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(approved.into_iter().map(|m| m.into()).collect())
+    }
+}
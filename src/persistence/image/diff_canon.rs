@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+
+use crate::{
+    domain::{
+        actions::image::DiffCanon,
+        models::{CanonDiff, DimensionChange, ImageFile},
+    },
+    persistence::{
+        image::update_canon::{determine_dimm_active_values, match_canon, CanonMatch},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl DiffCanon for PersistenceManager {
+    async fn diff_canon<'a, T: Iterator<Item = &'a ImageFile> + Send>(
+        &self,
+        canon: T,
+        kept_file_names: &HashSet<String>,
+    ) -> Result<CanonDiff, String> {
+        let CanonMatch {
+            saved,
+            unsaved,
+            deleted,
+            ..
+        } = match_canon(&self.db_conn, canon, kept_file_names)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let dimension_changes = saved
+            .into_iter()
+            .filter_map(|(image_file, (model, _))| {
+                let image = &image_file.image;
+                determine_dimm_active_values(
+                    (model.width, model.height),
+                    (image.width as i32, image.height as i32),
+                )
+                .map(|_| DimensionChange {
+                    file_name: model.file_name,
+                    saved: (model.width as u32, model.height as u32),
+                    current: (image.width, image.height),
+                })
+            })
+            .collect();
+
+        Ok(CanonDiff {
+            inserts: unsaved
+                .into_iter()
+                .map(|i| i.image.file_name.clone())
+                .collect(),
+            deletes: deleted
+                .into_iter()
+                .map(|(_, file_name)| file_name)
+                .collect(),
+            dimension_changes,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::image::FetchApprovedImage, models::Image},
+    persistence::{
+        entities::{images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchApprovedImage for PersistenceManager {
+    async fn fetch_approved_image(&self, file_name: &str) -> Result<Option<Image>, String> {
+        Ok(Images::find()
+            .filter(images::Column::FileName.eq(file_name))
+            .filter(images::Column::PendingApproval.eq(false))
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|m| m.into()))
+    }
+}
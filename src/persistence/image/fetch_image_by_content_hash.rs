@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::image::FetchImageByContentHash, models::Image},
+    persistence::{
+        entities::{images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchImageByContentHash for PersistenceManager {
+    async fn fetch_image_by_content_hash(
+        &self,
+        content_hash: &str,
+    ) -> Result<Option<Image>, String> {
+        Ok(Images::find()
+            .filter(images::Column::ContentHash.eq(content_hash))
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|m| m.into()))
+    }
+}
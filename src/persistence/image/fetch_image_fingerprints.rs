@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+
+use crate::{
+    domain::{
+        actions::image::FetchImageFingerprints,
+        models::{Image, ImageFingerprint},
+    },
+    persistence::{
+        entities::{images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchImageFingerprints for PersistenceManager {
+    async fn fetch_image_fingerprints(&self) -> Result<Vec<ImageFingerprint>, String> {
+        // Only the needed columns are selected, since every saved image is fetched.
+        let rows: Vec<(String, i32, i32, i64, NaiveDateTime)> = Images::find()
+            .select_only()
+            .columns([
+                images::Column::FileName,
+                images::Column::Width,
+                images::Column::Height,
+                images::Column::ByteSize,
+                images::Column::FileModifiedAt,
+            ])
+            .filter(images::Column::ByteSize.is_not_null())
+            .filter(images::Column::FileModifiedAt.is_not_null())
+            .filter(images::Column::PendingApproval.eq(false))
+            .into_tuple()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(file_name, width, height, byte_size, modified_at)| ImageFingerprint {
+                    image: Image {
+                        file_name,
+                        width: width as u32,
+                        height: height as u32,
+                    },
+                    byte_size: byte_size as u64,
+                    modified_at,
+                },
+            )
+            .collect())
+    }
+}
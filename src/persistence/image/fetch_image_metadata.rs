@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::image::FetchImageMetadata, models::ImageMetadata},
+    persistence::{
+        entities::{image_metadata, images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchImageMetadata for PersistenceManager {
+    async fn fetch_image_metadata(&self, file_name: &str) -> Result<Option<ImageMetadata>, String> {
+        let image = Images::find()
+            .filter(images::Column::FileName.eq(file_name))
+            .find_also_related(image_metadata::Entity)
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // Images saved before their metadata was extracted don't have a row yet.
+        Ok(image.map(|(_, metadata)| metadata.map(Into::into).unwrap_or_default()))
+    }
+}
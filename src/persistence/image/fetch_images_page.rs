@@ -1,16 +1,19 @@
-use std::cmp::Reverse;
+use std::{cmp::Reverse, collections::HashSet};
 
 use async_trait::async_trait;
-use sea_orm::{sea_query::PostgresQueryBuilder, CursorTrait, EntityTrait, QueryOrder};
+use sea_orm::{
+    sea_query::{Expr, PostgresQueryBuilder, Query, SelectStatement},
+    ColumnTrait, ConnectionTrait, CursorTrait, EntityTrait, QueryFilter, QueryOrder, Select,
+};
 use tracing::debug;
 
 use crate::{
     domain::{
         actions::image::{FetchImagesPage, PaginationOrder},
-        models::ImagesPage,
+        models::{ImagesPage, TagFilter},
     },
     persistence::{
-        entities::{images, prelude::Images},
+        entities::{image_tags, images, prelude::Images, tags},
         PersistenceManager,
     },
 };
@@ -22,34 +25,81 @@ impl FetchImagesPage for PersistenceManager {
         count: u64,
         cursor_value: Option<i32>,
         order: PaginationOrder,
+        tag_filter: &TagFilter,
     ) -> Result<ImagesPage, String> {
-        let mut cursor = Images::find().cursor_by(images::Column::Id);
-        if let Some(cursor_value) = cursor_value {
-            match order {
-                PaginationOrder::NewToOld => cursor.before(cursor_value),
-                PaginationOrder::OldToNew => cursor.after(cursor_value),
-            };
+        let mut select = Images::find().filter(images::Column::PendingApproval.eq(false));
+        if !tag_filter.any_of.is_empty() {
+            select = select.filter(
+                images::Column::Id.in_subquery(select_image_ids_with_tags(&tag_filter.any_of)),
+            );
         }
-        match order {
-            PaginationOrder::NewToOld => cursor.last(count),
-            PaginationOrder::OldToNew => cursor.first(count),
-        };
+        if !tag_filter.all_of.is_empty() {
+            let names: HashSet<_> = tag_filter.all_of.iter().collect();
+            let len = names.len() as i64;
+            // The primary key of `image_tags` ensures each tag is only counted once per image.
+            let query = select_image_ids_with_tags(names)
+                .group_by_col((image_tags::Entity, image_tags::Column::ImageId))
+                .and_having(
+                    Expr::col((image_tags::Entity, image_tags::Column::TagId))
+                        .count()
+                        .eq(len),
+                )
+                .to_owned();
+            select = select.filter(images::Column::Id.in_subquery(query));
+        }
+
+        fetch_page(select, &self.db_conn, count, cursor_value, order).await
+    }
+}
 
-        let query = cursor.query().to_string(PostgresQueryBuilder);
-        debug!("query: {}", query);
+/// Selects the ids of images that have at least one of the tags with the given names.
+fn select_image_ids_with_tags<'a>(names: impl IntoIterator<Item = &'a String>) -> SelectStatement {
+    Query::select()
+        .column((image_tags::Entity, image_tags::Column::ImageId))
+        .from(image_tags::Entity)
+        .inner_join(
+            tags::Entity,
+            Expr::col((tags::Entity, tags::Column::Id))
+                .equals((image_tags::Entity, image_tags::Column::TagId)),
+        )
+        .and_where(Expr::col((tags::Entity, tags::Column::Name)).is_in(names.into_iter().cloned()))
+        .to_owned()
+}
 
-        let mut images = cursor.all(&self.db_conn).await.map_err(|e| e.to_string())?;
+/// Fetches a page of the images selected by `select`, using the image id as the cursor.
+pub async fn fetch_page(
+    select: Select<Images>,
+    db: &impl ConnectionTrait,
+    count: u64,
+    cursor_value: Option<i32>,
+    order: PaginationOrder,
+) -> Result<ImagesPage, String> {
+    let mut cursor = select.cursor_by(images::Column::Id);
+    if let Some(cursor_value) = cursor_value {
         match order {
-            PaginationOrder::NewToOld => images.sort_by_key(|i| Reverse(i.id)),
-            PaginationOrder::OldToNew => images.sort_by_key(|i| i.id),
+            PaginationOrder::NewToOld => cursor.before(cursor_value),
+            PaginationOrder::OldToNew => cursor.after(cursor_value),
         };
-        for image in images.iter() {
-            debug!("id: {}, name: {}", image.id, &image.file_name);
-        }
+    }
+    match order {
+        PaginationOrder::NewToOld => cursor.last(count),
+        PaginationOrder::OldToNew => cursor.first(count),
+    };
+
+    let query = cursor.query().to_string(PostgresQueryBuilder);
+    debug!("query: {}", query);
 
-        Ok(ImagesPage {
-            cursor: images.last().map(|v| v.id),
-            images: images.into_iter().map(|m| m.into()).collect(),
-        })
+    let mut images = cursor.all(db).await.map_err(|e| e.to_string())?;
+    match order {
+        PaginationOrder::NewToOld => images.sort_by_key(|i| Reverse(i.id)),
+        PaginationOrder::OldToNew => images.sort_by_key(|i| i.id),
+    };
+    for image in images.iter() {
+        debug!("id: {}, name: {}", image.id, &image.file_name);
     }
+
+    Ok(ImagesPage {
+        cursor: images.last().map(|v| v.id),
+        images: images.into_iter().map(|m| m.into()).collect(),
+    })
 }
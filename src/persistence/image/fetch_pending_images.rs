@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    domain::{actions::image::FetchPendingImages, models::PendingImage},
+    persistence::{
+        entities::{images, prelude::GuestLinks, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchPendingImages for PersistenceManager {
+    async fn fetch_pending_images(&self) -> Result<Vec<PendingImage>, String> {
+        let models = Images::find()
+            .filter(images::Column::PendingApproval.eq(true))
+            .find_also_related(GuestLinks)
+            .order_by_asc(images::Column::Id)
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(models
+            .into_iter()
+            .map(|(image, link)| PendingImage {
+                image: image.into(),
+                guest_link_label: link.and_then(|l| l.label),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::{actions::image::FetchPerceptualHashes, models::Image},
+    persistence::{
+        entities::{images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchPerceptualHashes for PersistenceManager {
+    async fn fetch_perceptual_hashes(&self) -> Result<Vec<(Image, u64)>, String> {
+        let models = Images::find()
+            .filter(images::Column::PerceptualHash.is_not_null())
+            .filter(images::Column::PendingApproval.eq(false))
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(models
+            .into_iter()
+            .filter_map(|m| {
+                let perceptual_hash = m.perceptual_hash? as u64;
+                Some((m.into(), perceptual_hash))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,32 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QuerySelect};
+
+use crate::{
+    domain::actions::image::FetchScreensaverExclusions,
+    persistence::{
+        entities::{images, prelude::Images},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchScreensaverExclusions for PersistenceManager {
+    async fn fetch_screensaver_exclusions(&self) -> Result<HashSet<String>, String> {
+        let file_names: Vec<String> = Images::find()
+            .select_only()
+            .column(images::Column::FileName)
+            .filter(
+                Condition::any()
+                    .add(images::Column::ExcludedFromScreensaver.eq(true))
+                    .add(images::Column::PendingApproval.eq(true)),
+            )
+            .into_tuple()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(file_names.into_iter().collect())
+    }
+}
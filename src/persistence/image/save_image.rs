@@ -1,23 +1,95 @@
 use async_trait::async_trait;
-use sea_orm::EntityTrait;
+use sea_orm::{ActiveValue, ColumnTrait, DbErr, EntityTrait, QueryFilter, TransactionTrait};
 
 use crate::{
-    domain::{actions::image::SaveImage, models::Image},
+    domain::{
+        actions::image::{SaveImage, SaveImageError},
+        models::{ImageFile, UploadDetails},
+    },
     persistence::{
-        entities::{images, prelude::Images},
-        image::active_model_for_insert_from,
+        album::fetch_album_model,
+        entities::{
+            album_images, guest_links,
+            prelude::{AlbumImages, GuestLinks, ImageMetadata, Images},
+        },
+        image::{active_model_for_insert_from, metadata_active_model_from},
+        tag::insert_image_tags,
+        upload_batch::upsert_upload_batch,
         PersistenceManager,
     },
 };
 
 #[async_trait]
 impl SaveImage for PersistenceManager {
-    async fn save_image(&self, image: &Image) -> Result<(), String> {
-        let model: images::ActiveModel = active_model_for_insert_from(image);
-        Images::insert(model)
-            .exec(&self.db_conn)
+    async fn save_image(
+        &self,
+        image: &ImageFile,
+        details: &UploadDetails,
+    ) -> Result<(), SaveImageError> {
+        let album_id = match &details.album {
+            Some(name) => Some(
+                fetch_album_model(&self.db_conn, name)
+                    .await
+                    .map_err(|e| SaveImageError::Persistence(e.to_string()))?
+                    .ok_or(SaveImageError::AlbumNotFound)?
+                    .id,
+            ),
+            None => None,
+        };
+        let guest_link_id = match &details.guest_link {
+            Some(token) => Some(
+                GuestLinks::find()
+                    .filter(guest_links::Column::Token.eq(token.as_str()))
+                    .one(&self.db_conn)
+                    .await
+                    .map_err(|e| SaveImageError::Persistence(e.to_string()))?
+                    .ok_or(SaveImageError::GuestLinkNotFound)?
+                    .id,
+            ),
+            None => None,
+        };
+
+        let mut model = active_model_for_insert_from(image);
+        model.caption = ActiveValue::Set(details.caption.clone());
+        model.excluded_from_screensaver = ActiveValue::Set(details.excluded_from_screensaver);
+        // Guest uploads are only shown once they're approved.
+        model.guest_link_id = ActiveValue::Set(guest_link_id);
+        model.pending_approval = ActiveValue::Set(guest_link_id.is_some());
+        let mut metadata = image.metadata.clone();
+        if details.taken_at.is_some() {
+            metadata.taken_at = details.taken_at;
+        }
+        let taken_at_overridden = details.taken_at.is_some();
+        let tags = details.tags.clone();
+        let upload_batch = details.upload_batch.clone();
+        self.db_conn
+            .transaction::<_, (), DbErr>(|txn| {
+                Box::pin(async move {
+                    if let Some(name) = upload_batch {
+                        let batch_id = upsert_upload_batch(txn, &name).await?;
+                        model.upload_batch_id = ActiveValue::Set(Some(batch_id));
+                    }
+                    let image_id = Images::insert(model).exec(txn).await?.last_insert_id;
+                    let mut metadata = metadata_active_model_from(image_id, &metadata);
+                    metadata.taken_at_overridden = ActiveValue::Set(taken_at_overridden);
+                    ImageMetadata::insert(metadata).exec(txn).await?;
+                    if !tags.is_empty() {
+                        insert_image_tags(txn, image_id, &tags).await?;
+                    }
+                    if let Some(album_id) = album_id {
+                        AlbumImages::insert(album_images::ActiveModel {
+                            album_id: ActiveValue::Set(album_id),
+                            image_id: ActiveValue::Set(image_id),
+                        })
+                        .exec(txn)
+                        .await?;
+                    }
+
+                    Ok(())
+                })
+            })
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| SaveImageError::Persistence(e.to_string()))?;
 
         Ok(())
     }
@@ -1,78 +1,200 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use async_trait::async_trait;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DbErr, EntityTrait, QueryFilter, TransactionTrait,
+    sea_query::{Expr, OnConflict},
+    ActiveValue, ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, Statement, TransactionTrait, Value,
 };
 
 use crate::{
-    domain::{actions::image::UpdateCanon, models::Image},
+    domain::{
+        actions::image::UpdateCanon,
+        models::{ImageFile, ImageMetadata},
+    },
     persistence::{
-        entities::{images, prelude::Images},
-        image::active_model_for_insert_from,
+        entities::{image_metadata, images, prelude::Images},
+        image::{active_model_for_insert_from, metadata_active_model_from},
         PersistenceManager,
     },
 };
 
+/// How many rows are read or written by one statement, to stay well under the bind parameter
+/// limit of Postgres with the columns of images and of their metadata.
+const CHUNK_SIZE: usize = 1000;
+
 #[async_trait]
 impl UpdateCanon for PersistenceManager {
-    async fn update_canon<'a, T: Iterator<Item = &'a Image> + Send>(
+    async fn update_canon<'a, T: Iterator<Item = &'a ImageFile> + Send>(
         &self,
         canon: T,
-    ) -> Result<(), String> {
-        let mut models = {
-            let models = Images::find()
-                .all(&self.db_conn)
-                .await
-                .map_err(|e| e.to_string())?;
-            let mut model_map = HashMap::new();
-            for model in models {
-                model_map.insert(model.file_name.clone(), model);
+        kept_file_names: &HashSet<String>,
+    ) -> Result<Vec<String>, String> {
+        let CanonMatch {
+            saved,
+            unsaved,
+            deleted,
+            kept_content_hashes,
+        } = match_canon(&self.db_conn, canon, kept_file_names)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut claimed_hashes = kept_content_hashes;
+        claimed_hashes.extend(
+            saved
+                .iter()
+                .filter(|(f, (model, _))| model.content_hash.as_ref() == Some(&f.content_hash))
+                .map(|(f, _)| f.content_hash.clone()),
+        );
+        let mut claim_hash = |image_file: &ImageFile| {
+            if claimed_hashes.insert(image_file.content_hash.clone()) {
+                Some(image_file.content_hash.clone())
+            } else {
+                tracing::warn!(
+                    "{} has the same content as another image, so its content hash isn't saved",
+                    image_file.image.file_name
+                );
+                None
             }
-            model_map
         };
 
-        let canon: Vec<_> = canon.collect();
         let mut updates = Vec::new();
-        let mut inserts: Vec<images::ActiveModel> = Vec::new();
-        for image in canon {
-            let model = models.remove(&image.file_name);
-            if let Some(model) = model {
-                let image_width = image.width as i32;
-                let image_height = image.height as i32;
-                let dimm_active_values = determine_dimm_active_values(
-                    (model.width, model.height),
-                    (image_width, image_height),
-                );
-                if let Some((width, height)) = dimm_active_values {
-                    updates.push(images::ActiveModel {
-                        id: ActiveValue::Unchanged(model.id),
-                        file_name: ActiveValue::Unchanged(model.file_name),
-                        width,
-                        height,
-                    });
-                }
+        // Ids of images whose saved content hash changes. They're cleared before the updates, so
+        // images swapping content don't conflict.
+        let mut cleared_hash_ids = Vec::new();
+        let mut metadata_upserts = Vec::new();
+        for (image_file, (model, saved_metadata)) in saved {
+            let ImageFile {
+                image, metadata, ..
+            } = image_file;
+            let content_hash = if model.content_hash.as_ref() == Some(&image_file.content_hash) {
+                ActiveValue::Unchanged(model.content_hash.clone())
             } else {
-                inserts.push(active_model_for_insert_from(image));
+                if model.content_hash.is_some() {
+                    cleared_hash_ids.push(model.id);
+                }
+                match claim_hash(image_file) {
+                    None if model.content_hash.is_none() => ActiveValue::Unchanged(None),
+                    content_hash => ActiveValue::Set(content_hash),
+                }
+            };
+
+            let image_width = image.width as i32;
+            let image_height = image.height as i32;
+            let dimm_active_values = determine_dimm_active_values(
+                (model.width, model.height),
+                (image_width, image_height),
+            );
+            // A `taken_at` given with the upload is kept over the one in the EXIF.
+            let mut metadata = metadata.clone();
+            if let Some(saved_metadata) = saved_metadata.as_ref() {
+                if saved_metadata.taken_at_overridden {
+                    metadata.taken_at = saved_metadata.taken_at;
+                }
+            }
+            if saved_metadata.map(ImageMetadata::from).as_ref() != Some(&metadata) {
+                metadata_upserts.push(metadata_active_model_from(model.id, &metadata));
+            }
+            let perceptual_hash = active_value_of(
+                model.perceptual_hash,
+                image_file.perceptual_hash.map(|v| v as i64),
+            );
+            let format = active_value_of(model.format, image_file.format.clone());
+            let mime_type = active_value_of(model.mime_type, image_file.mime_type.clone());
+            let byte_size = active_value_of(model.byte_size, Some(image_file.byte_size as i64));
+            let file_modified_at = active_value_of(model.file_modified_at, image_file.modified_at);
+            if dimm_active_values.is_some()
+                || content_hash.is_set()
+                || perceptual_hash.is_set()
+                || format.is_set()
+                || mime_type.is_set()
+                || byte_size.is_set()
+                || file_modified_at.is_set()
+            {
+                let (width, height) = dimm_active_values.unwrap_or((
+                    ActiveValue::Unchanged(model.width),
+                    ActiveValue::Unchanged(model.height),
+                ));
+                updates.push(images::ActiveModel {
+                    id: ActiveValue::Unchanged(model.id),
+                    file_name: ActiveValue::Unchanged(model.file_name),
+                    width,
+                    height,
+                    content_hash,
+                    perceptual_hash,
+                    format,
+                    mime_type,
+                    byte_size,
+                    file_modified_at,
+                    caption: ActiveValue::Unchanged(model.caption),
+                    excluded_from_screensaver: ActiveValue::Unchanged(
+                        model.excluded_from_screensaver,
+                    ),
+                    upload_batch_id: ActiveValue::Unchanged(model.upload_batch_id),
+                    pending_approval: ActiveValue::Unchanged(model.pending_approval),
+                    guest_link_id: ActiveValue::Unchanged(model.guest_link_id),
+                });
             }
         }
 
-        let delete_ids: Vec<_> = models.into_iter().map(|e| e.1.id).collect();
+        let mut inserts: Vec<images::ActiveModel> = Vec::new();
+        // Metadata of inserted images, keyed by file name since their ids aren't known yet.
+        let mut inserted_metadata = HashMap::new();
+        for image_file in unsaved {
+            let mut model = active_model_for_insert_from(image_file);
+            model.content_hash = ActiveValue::Set(claim_hash(image_file));
+            inserts.push(model);
+            inserted_metadata.insert(
+                image_file.image.file_name.clone(),
+                image_file.metadata.clone(),
+            );
+        }
+
+        let (delete_ids, deleted_file_names): (Vec<_>, Vec<_>) = deleted.into_iter().unzip();
 
         self.db_conn
             .transaction::<_, (), DbErr>(|txn| {
                 Box::pin(async move {
-                    if !delete_ids.is_empty() {
+                    for ids in delete_ids.chunks(CHUNK_SIZE) {
                         Images::delete_many()
-                            .filter(images::Column::Id.is_in(delete_ids))
+                            .filter(images::Column::Id.is_in(ids.iter().copied()))
+                            .exec(txn)
+                            .await?;
+                    }
+                    for ids in cleared_hash_ids.chunks(CHUNK_SIZE) {
+                        Images::update_many()
+                            .col_expr(
+                                images::Column::ContentHash,
+                                Expr::value(Value::String(None)),
+                            )
+                            .filter(images::Column::Id.is_in(ids.iter().copied()))
                             .exec(txn)
                             .await?;
                     }
-                    if !inserts.is_empty() {
+                    for updates in into_chunks(updates) {
+                        update_images(updates, txn).await?;
+                    }
+                    for inserts in into_chunks(inserts) {
                         Images::insert_many(inserts).exec(txn).await?;
                     }
-                    for update in updates {
-                        update.update(txn).await?;
+
+                    let inserted_file_names: Vec<_> = inserted_metadata.keys().collect();
+                    for file_names in inserted_file_names.chunks(CHUNK_SIZE) {
+                        let inserted: Vec<(i32, String)> = Images::find()
+                            .select_only()
+                            .columns([images::Column::Id, images::Column::FileName])
+                            .filter(images::Column::FileName.is_in(file_names.iter().copied()))
+                            .into_tuple()
+                            .all(txn)
+                            .await?;
+                        for (id, file_name) in inserted {
+                            if let Some(metadata) = inserted_metadata.get(&file_name) {
+                                metadata_upserts.push(metadata_active_model_from(id, metadata));
+                            }
+                        }
+                    }
+                    for metadata_upserts in into_chunks(metadata_upserts) {
+                        upsert_metadata(metadata_upserts, txn).await?;
                     }
 
                     Ok(())
@@ -81,11 +203,231 @@ impl UpdateCanon for PersistenceManager {
             .await
             .map_err(|e| e.to_string())?;
 
-        Ok(())
+        Ok(deleted_file_names)
     }
 }
 
-fn determine_dimm_active_values(
+/// The saved images matched against the files of the canon.
+pub(super) struct CanonMatch<'a> {
+    /// The files of saved images, with their saved rows. Ordered by id.
+    pub saved: Vec<(
+        &'a ImageFile,
+        (images::Model, Option<image_metadata::Model>),
+    )>,
+    /// The files without saved images. Ordered by file name.
+    pub unsaved: Vec<&'a ImageFile>,
+    /// The ids and file names of the saved images without files, other than the kept ones.
+    /// Ordered by id.
+    pub deleted: Vec<(i32, String)>,
+    /// The content hashes of the kept images, which stay claimed by them.
+    pub kept_content_hashes: HashSet<String>,
+}
+
+/// Matches the saved images against the canon by file name. The images saved under the
+/// `kept_file_names` are left out.
+pub(super) async fn match_canon<'a>(
+    db: &impl ConnectionTrait,
+    canon: impl Iterator<Item = &'a ImageFile>,
+    kept_file_names: &HashSet<String>,
+) -> Result<CanonMatch<'a>, DbErr> {
+    // Only the names of every saved image are fetched. The rest of the rows is fetched for the
+    // images in the canon, which are usually a few when most files are unchanged.
+    let rows: Vec<(String, i32, Option<String>, bool)> = Images::find()
+        .select_only()
+        .columns([
+            images::Column::FileName,
+            images::Column::Id,
+            images::Column::ContentHash,
+            images::Column::PendingApproval,
+        ])
+        .into_tuple()
+        .all(db)
+        .await?;
+    let mut content_hashes = HashMap::new();
+    let mut saved_ids = HashMap::new();
+    // Images pending approval aren't in the canon, so they're kept as they are.
+    let mut pending_file_names = HashSet::new();
+    for (file_name, id, content_hash, pending_approval) in rows {
+        if let Some(content_hash) = content_hash {
+            content_hashes.insert(id, content_hash);
+        }
+        if pending_approval {
+            pending_file_names.insert(file_name.clone());
+        }
+        saved_ids.insert(file_name, id);
+    }
+
+    let mut unsaved = Vec::new();
+    let mut matched = HashMap::new();
+    for image_file in canon {
+        // Like one being approved, which is moved into the canon before it's saved as approved.
+        if pending_file_names.contains(&image_file.image.file_name) {
+            continue;
+        }
+        match saved_ids.remove(&image_file.image.file_name) {
+            Some(id) => {
+                matched.insert(id, image_file);
+            }
+            None => unsaved.push(image_file),
+        }
+    }
+
+    // Saved images come first, ordered by id, so they keep their content hashes over new
+    // images with the same content.
+    let mut saved = Vec::new();
+    let ids: Vec<_> = matched.keys().copied().collect();
+    for ids in ids.chunks(CHUNK_SIZE) {
+        let models = Images::find()
+            .find_also_related(image_metadata::Entity)
+            .filter(images::Column::Id.is_in(ids.iter().copied()))
+            .all(db)
+            .await?;
+        for (model, metadata) in models {
+            if let Some(image_file) = matched.remove(&model.id) {
+                saved.push((image_file, (model, metadata)));
+            }
+        }
+    }
+    // Rows deleted since their names were fetched are saved again.
+    unsaved.extend(matched.into_values());
+    saved.sort_by_key(|(_, (model, _))| model.id);
+    unsaved.sort_by(|a, b| a.image.file_name.cmp(&b.image.file_name));
+
+    let (kept, mut deleted): (Vec<_>, Vec<_>) = saved_ids
+        .into_iter()
+        .map(|(file_name, id)| (id, file_name))
+        .partition(|(_, file_name)| {
+            kept_file_names.contains(file_name) || pending_file_names.contains(file_name)
+        });
+    deleted.sort();
+    let kept_content_hashes = kept
+        .into_iter()
+        .filter_map(|(id, _)| content_hashes.remove(&id))
+        .collect();
+
+    Ok(CanonMatch {
+        saved,
+        unsaved,
+        deleted,
+        kept_content_hashes,
+    })
+}
+
+/// Updates the images in one statement. Every column of the images read from their files is
+/// given, along with their ids. Images deleted in the meantime are left deleted.
+async fn update_images(
+    models: Vec<images::ActiveModel>,
+    db: &impl ConnectionTrait,
+) -> Result<(), DbErr> {
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    // The casts type the columns of the values, which may all be null.
+    const ROW_TYPES: [&str; 9] = [
+        "integer",
+        "integer",
+        "integer",
+        "text",
+        "bigint",
+        "text",
+        "text",
+        "bigint",
+        "timestamp",
+    ];
+    let mut rows = Vec::with_capacity(models.len());
+    let mut values = Vec::with_capacity(models.len() * ROW_TYPES.len());
+    for model in models {
+        let row = ROW_TYPES
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| format!("${}::{}", values.len() + i + 1, ty))
+            .collect::<Vec<_>>();
+        rows.push(format!("({})", row.join(", ")));
+        values.extend(
+            [
+                model.id.into_value(),
+                model.width.into_value(),
+                model.height.into_value(),
+                model.content_hash.into_value(),
+                model.perceptual_hash.into_value(),
+                model.format.into_value(),
+                model.mime_type.into_value(),
+                model.byte_size.into_value(),
+                model.file_modified_at.into_value(),
+            ]
+            .map(|v| v.expect("every column of an updated image should be given")),
+        );
+    }
+    let sql = format!(
+        "UPDATE images SET width = v.width, height = v.height, content_hash = v.content_hash, \
+         perceptual_hash = v.perceptual_hash, format = v.format, mime_type = v.mime_type, \
+         byte_size = v.byte_size, file_modified_at = v.file_modified_at \
+         FROM (VALUES {}) AS v(id, width, height, content_hash, perceptual_hash, format, \
+         mime_type, byte_size, file_modified_at) \
+         WHERE images.id = v.id",
+        rows.join(", ")
+    );
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &sql,
+        values,
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Splits the items into chunks of at most [CHUNK_SIZE].
+fn into_chunks<T>(items: Vec<T>) -> impl Iterator<Item = Vec<T>> {
+    let mut items = items.into_iter().peekable();
+    std::iter::from_fn(move || {
+        items.peek()?;
+        Some(items.by_ref().take(CHUNK_SIZE).collect())
+    })
+}
+
+/// Inserts the metadata, replacing any existing metadata of the same images.
+async fn upsert_metadata(
+    models: Vec<image_metadata::ActiveModel>,
+    db: &impl ConnectionTrait,
+) -> Result<(), DbErr> {
+    let on_conflict = OnConflict::column(image_metadata::Column::ImageId)
+        .update_columns([
+            image_metadata::Column::TakenAt,
+            image_metadata::Column::CameraMake,
+            image_metadata::Column::CameraModel,
+            image_metadata::Column::LensModel,
+            image_metadata::Column::ExposureTime,
+            image_metadata::Column::FNumber,
+            image_metadata::Column::Iso,
+            image_metadata::Column::FocalLength,
+            image_metadata::Column::Orientation,
+            image_metadata::Column::GpsLatitude,
+            image_metadata::Column::GpsLongitude,
+        ])
+        .to_owned();
+    image_metadata::Entity::insert_many(models)
+        .on_conflict(on_conflict)
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the `current` value, set only if it differs from the `saved` one.
+fn active_value_of<T>(saved: T, current: T) -> ActiveValue<T>
+where
+    T: Into<Value> + PartialEq,
+{
+    if saved == current {
+        ActiveValue::Unchanged(current)
+    } else {
+        ActiveValue::Set(current)
+    }
+}
+
+pub(super) fn determine_dimm_active_values(
     model_dimm: (i32, i32),
     image_dimm: (i32, i32),
 ) -> Option<(ActiveValue<i32>, ActiveValue<i32>)> {
@@ -105,3 +447,22 @@ fn determine_dimm_active_values(
         Some((width, height))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_chunks_splits_into_bounded_chunks() {
+        let items: Vec<_> = (0..CHUNK_SIZE * 2 + 1).collect();
+
+        let chunks: Vec<_> = into_chunks(items.clone()).collect();
+
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            [CHUNK_SIZE, CHUNK_SIZE, 1]
+        );
+        assert_eq!(chunks.concat(), items);
+        assert_eq!(into_chunks(Vec::<u8>::new()).count(), 0);
+    }
+}
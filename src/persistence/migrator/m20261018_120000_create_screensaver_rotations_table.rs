@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScreensaverRotations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScreensaverRotations::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ScreensaverRotations::DeviceId)
+                            .string()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ScreensaverRotations::FileNames)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScreensaverRotations::CurrentIndex).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScreensaverRotations::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ScreensaverRotations {
+    Table,
+    Id,
+    DeviceId,
+    FileNames,
+    CurrentIndex,
+}
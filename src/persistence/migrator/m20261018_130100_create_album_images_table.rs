@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AlbumImages::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(AlbumImages::AlbumId).integer().not_null())
+                    .col(ColumnDef::new(AlbumImages::ImageId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(AlbumImages::AlbumId)
+                            .col(AlbumImages::ImageId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(AlbumImages::Table, AlbumImages::AlbumId)
+                            .to(Albums::Table, Albums::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(AlbumImages::Table, AlbumImages::ImageId)
+                            .to(Images::Table, Images::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(AlbumImages::Table)
+                    .name("idx-album_images-image_id")
+                    .col(AlbumImages::ImageId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AlbumImages::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AlbumImages {
+    Table,
+    AlbumId,
+    ImageId,
+}
+
+#[derive(Iden)]
+enum Albums {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Images {
+    Table,
+    Id,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImageTags::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ImageTags::ImageId).integer().not_null())
+                    .col(ColumnDef::new(ImageTags::TagId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ImageTags::ImageId)
+                            .col(ImageTags::TagId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ImageTags::Table, ImageTags::ImageId)
+                            .to(Images::Table, Images::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ImageTags::Table, ImageTags::TagId)
+                            .to(Tags::Table, Tags::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ImageTags::Table)
+                    .name("idx-image_tags-tag_id")
+                    .col(ImageTags::TagId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImageTags::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ImageTags {
+    Table,
+    ImageId,
+    TagId,
+}
+
+#[derive(Iden)]
+enum Images {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Tags {
+    Table,
+    Id,
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImageMetadata::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImageMetadata::ImageId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ImageMetadata::TakenAt).timestamp())
+                    .col(ColumnDef::new(ImageMetadata::CameraMake).string())
+                    .col(ColumnDef::new(ImageMetadata::CameraModel).string())
+                    .col(ColumnDef::new(ImageMetadata::LensModel).string())
+                    .col(ColumnDef::new(ImageMetadata::ExposureTime).double())
+                    .col(ColumnDef::new(ImageMetadata::FNumber).double())
+                    .col(ColumnDef::new(ImageMetadata::Iso).integer())
+                    .col(ColumnDef::new(ImageMetadata::FocalLength).double())
+                    .col(ColumnDef::new(ImageMetadata::Orientation).small_integer())
+                    .col(ColumnDef::new(ImageMetadata::GpsLatitude).double())
+                    .col(ColumnDef::new(ImageMetadata::GpsLongitude).double())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ImageMetadata::Table, ImageMetadata::ImageId)
+                            .to(Images::Table, Images::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImageMetadata::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ImageMetadata {
+    Table,
+    ImageId,
+    TakenAt,
+    CameraMake,
+    CameraModel,
+    LensModel,
+    ExposureTime,
+    FNumber,
+    Iso,
+    FocalLength,
+    Orientation,
+    GpsLatitude,
+    GpsLongitude,
+}
+
+#[derive(Iden)]
+enum Images {
+    Table,
+    Id,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotentResponses::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IdempotentResponses::Scope)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IdempotentResponses::Key).string().not_null())
+                    .col(
+                        ColumnDef::new(IdempotentResponses::StatusCode)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IdempotentResponses::ContentType).string())
+                    .col(ColumnDef::new(IdempotentResponses::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(IdempotentResponses::SavedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(IdempotentResponses::Scope)
+                            .col(IdempotentResponses::Key),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(IdempotentResponses::Table)
+                    .name("idx-idempotent_responses-saved_at")
+                    .col(IdempotentResponses::SavedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotentResponses::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum IdempotentResponses {
+    Table,
+    Scope,
+    Key,
+    StatusCode,
+    ContentType,
+    Body,
+    SavedAt,
+}
@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UploadBatches::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UploadBatches::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UploadBatches::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UploadBatches::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Images found on disk by updating the canon don't belong to any batch.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Images::Table)
+                    .add_column(ColumnDef::new(Images::UploadBatchId).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-images-upload_batch_id")
+                            .from_tbl(Images::Table)
+                            .from_col(Images::UploadBatchId)
+                            .to_tbl(UploadBatches::Table)
+                            .to_col(UploadBatches::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Images::Table)
+                    .name("idx-images-upload_batch_id")
+                    .col(Images::UploadBatchId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Images::Table)
+                    .drop_column(Images::UploadBatchId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UploadBatches::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum UploadBatches {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Images {
+    Table,
+    UploadBatchId,
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuestLinks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuestLinks::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuestLinks::Token)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(GuestLinks::Label).string())
+                    .col(ColumnDef::new(GuestLinks::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(GuestLinks::ExpiresAt).timestamp().not_null())
+                    .col(ColumnDef::new(GuestLinks::MaxUploads).integer().not_null())
+                    .col(
+                        ColumnDef::new(GuestLinks::MaxByteSize)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GuestLinks::UploadCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(GuestLinks::UploadedByteSize)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Guest uploads wait for approval before they're shown.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Images::Table)
+                    .add_column(
+                        ColumnDef::new(Images::PendingApproval)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(ColumnDef::new(Images::GuestLinkId).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-images-guest_link_id")
+                            .from_tbl(Images::Table)
+                            .from_col(Images::GuestLinkId)
+                            .to_tbl(GuestLinks::Table)
+                            .to_col(GuestLinks::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Images::Table)
+                    .drop_column(Images::GuestLinkId)
+                    .drop_column(Images::PendingApproval)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(GuestLinks::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum GuestLinks {
+    Table,
+    Id,
+    Token,
+    Label,
+    CreatedAt,
+    ExpiresAt,
+    MaxUploads,
+    MaxByteSize,
+    UploadCount,
+    UploadedByteSize,
+}
+
+#[derive(Iden)]
+enum Images {
+    Table,
+    PendingApproval,
+    GuestLinkId,
+}
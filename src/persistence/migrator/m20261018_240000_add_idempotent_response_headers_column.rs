@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The name and value pairs of the headers replayed along with the response. Responses
+        // saved before have none.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotentResponses::Table)
+                    .add_column(
+                        ColumnDef::new(IdempotentResponses::Headers)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'[]'::jsonb")),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotentResponses::Table)
+                    .drop_column(IdempotentResponses::Headers)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum IdempotentResponses {
+    Table,
+    Headers,
+}
@@ -5,6 +5,21 @@ use sea_orm_migration::prelude::*;
 mod m20230707_014242_create_images_table;
 mod m20230708_223818_add_image_size_columns;
 mod m20230708_231248_remove_width_and_height_defaults;
+mod m20261018_120000_create_screensaver_rotations_table;
+mod m20261018_130000_create_albums_table;
+mod m20261018_130100_create_album_images_table;
+mod m20261018_140000_create_tags_table;
+mod m20261018_140100_create_image_tags_table;
+mod m20261018_150000_create_image_metadata_table;
+mod m20261018_160000_add_image_content_hash_column;
+mod m20261018_170000_add_image_perceptual_hash_column;
+mod m20261018_180000_add_image_format_columns;
+mod m20261018_190000_add_image_upload_details_columns;
+mod m20261018_200000_create_idempotent_responses_table;
+mod m20261018_210000_create_upload_batches_table;
+mod m20261018_220000_create_guest_links_table;
+mod m20261018_230000_add_image_file_modified_at_column;
+mod m20261018_240000_add_idempotent_response_headers_column;
 
 pub struct Migrator;
 
@@ -15,6 +30,21 @@ impl MigratorTrait for Migrator {
             Box::new(m20230707_014242_create_images_table::Migration),
             Box::new(m20230708_223818_add_image_size_columns::Migration),
             Box::new(m20230708_231248_remove_width_and_height_defaults::Migration),
+            Box::new(m20261018_120000_create_screensaver_rotations_table::Migration),
+            Box::new(m20261018_130000_create_albums_table::Migration),
+            Box::new(m20261018_130100_create_album_images_table::Migration),
+            Box::new(m20261018_140000_create_tags_table::Migration),
+            Box::new(m20261018_140100_create_image_tags_table::Migration),
+            Box::new(m20261018_150000_create_image_metadata_table::Migration),
+            Box::new(m20261018_160000_add_image_content_hash_column::Migration),
+            Box::new(m20261018_170000_add_image_perceptual_hash_column::Migration),
+            Box::new(m20261018_180000_add_image_format_columns::Migration),
+            Box::new(m20261018_190000_add_image_upload_details_columns::Migration),
+            Box::new(m20261018_200000_create_idempotent_responses_table::Migration),
+            Box::new(m20261018_210000_create_upload_batches_table::Migration),
+            Box::new(m20261018_220000_create_guest_links_table::Migration),
+            Box::new(m20261018_230000_add_image_file_modified_at_column::Migration),
+            Box::new(m20261018_240000_add_idempotent_response_headers_column::Migration),
         ]
     }
 }
@@ -0,0 +1,31 @@
+use sea_orm::ActiveValue;
+
+use crate::{domain::models::ScreensaverRotation, persistence::entities::screensaver_rotations};
+
+pub mod delete_screensaver_rotations;
+pub mod fetch_screensaver_rotations;
+pub mod save_screensaver_cursors;
+pub mod save_screensaver_rotations;
+
+fn active_model_for_insert_from(
+    rotation: &ScreensaverRotation,
+) -> screensaver_rotations::ActiveModel {
+    screensaver_rotations::ActiveModel {
+        device_id: ActiveValue::Set(rotation.device_id.clone()),
+        file_names: ActiveValue::Set(rotation.file_names.clone().into()),
+        current_index: ActiveValue::Set(rotation.current_index.map(|i| i as i32)),
+        ..Default::default()
+    }
+}
+
+impl TryFrom<screensaver_rotations::Model> for ScreensaverRotation {
+    type Error = String;
+
+    fn try_from(value: screensaver_rotations::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            device_id: value.device_id,
+            file_names: serde_json::from_value(value.file_names).map_err(|e| e.to_string())?,
+            current_index: value.current_index.map(|i| i as usize),
+        })
+    }
+}
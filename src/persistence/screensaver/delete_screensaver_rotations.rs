@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::screensaver::DeleteScreensaverRotations,
+    persistence::{
+        entities::{prelude::ScreensaverRotations, screensaver_rotations},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl DeleteScreensaverRotations for PersistenceManager {
+    async fn delete_screensaver_rotations(&self, device_ids: &[String]) -> Result<(), String> {
+        if device_ids.is_empty() {
+            return Ok(());
+        }
+
+        ScreensaverRotations::delete_many()
+            .filter(screensaver_rotations::Column::DeviceId.is_in(device_ids.to_vec()))
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
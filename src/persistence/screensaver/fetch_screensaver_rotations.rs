@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use sea_orm::EntityTrait;
+
+use crate::{
+    domain::{actions::screensaver::FetchScreensaverRotations, models::ScreensaverRotation},
+    persistence::{entities::prelude::ScreensaverRotations, PersistenceManager},
+};
+
+#[async_trait]
+impl FetchScreensaverRotations for PersistenceManager {
+    async fn fetch_screensaver_rotations(&self) -> Result<Vec<ScreensaverRotation>, String> {
+        ScreensaverRotations::find()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|m| m.try_into())
+            .collect()
+    }
+}
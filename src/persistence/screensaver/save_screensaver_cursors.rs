@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use sea_orm::{ConnectionTrait, DbBackend, Statement, Value};
+
+use crate::{
+    domain::{actions::screensaver::SaveScreensaverCursors, models::ScreensaverCursor},
+    persistence::PersistenceManager,
+};
+
+#[async_trait]
+impl SaveScreensaverCursors for PersistenceManager {
+    async fn save_screensaver_cursors(&self, cursors: &[ScreensaverCursor]) -> Result<(), String> {
+        if cursors.is_empty() {
+            return Ok(());
+        }
+
+        // Every cursor is saved by one statement. The default rotation has a null device id,
+        // which is only matched by `IS NOT DISTINCT FROM`.
+        let mut rows = Vec::with_capacity(cursors.len());
+        let mut values: Vec<Value> = Vec::with_capacity(cursors.len() * 2);
+        for (i, cursor) in cursors.iter().enumerate() {
+            rows.push(format!("(${}::text, ${}::integer)", i * 2 + 1, i * 2 + 2));
+            values.push(cursor.device_id.clone().into());
+            values.push(cursor.current_index.map(|i| i as i32).into());
+        }
+        let sql = format!(
+            "UPDATE screensaver_rotations AS r SET current_index = v.current_index \
+             FROM (VALUES {}) AS v(device_id, current_index) \
+             WHERE r.device_id IS NOT DISTINCT FROM v.device_id",
+            rows.join(", ")
+        );
+
+        self.db_conn
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                &sql,
+                values,
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
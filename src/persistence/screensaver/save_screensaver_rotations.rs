@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter, TransactionTrait};
+
+use crate::{
+    domain::{actions::screensaver::SaveScreensaverRotations, models::ScreensaverRotation},
+    persistence::{
+        entities::{prelude::ScreensaverRotations, screensaver_rotations},
+        screensaver::active_model_for_insert_from,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl SaveScreensaverRotations for PersistenceManager {
+    async fn save_screensaver_rotations(
+        &self,
+        rotations: &[ScreensaverRotation],
+    ) -> Result<(), String> {
+        if rotations.is_empty() {
+            return Ok(());
+        }
+
+        // The default rotation has a null device id, which `is_in` can't match.
+        let mut condition = Condition::any();
+        let device_ids: Vec<_> = rotations
+            .iter()
+            .filter_map(|r| r.device_id.clone())
+            .collect();
+        if !device_ids.is_empty() {
+            condition = condition.add(screensaver_rotations::Column::DeviceId.is_in(device_ids));
+        }
+        if rotations.iter().any(|r| r.device_id.is_none()) {
+            condition = condition.add(screensaver_rotations::Column::DeviceId.is_null());
+        }
+        let models: Vec<_> = rotations.iter().map(active_model_for_insert_from).collect();
+
+        self.db_conn
+            .transaction::<_, (), DbErr>(|txn| {
+                Box::pin(async move {
+                    ScreensaverRotations::delete_many()
+                        .filter(condition)
+                        .exec(txn)
+                        .await?;
+                    ScreensaverRotations::insert_many(models).exec(txn).await?;
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
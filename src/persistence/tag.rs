@@ -0,0 +1,84 @@
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter,
+};
+
+use crate::{
+    domain::models::Tag,
+    persistence::entities::{
+        image_tags, images,
+        prelude::{ImageTags, Images, Tags},
+        tags,
+    },
+};
+
+pub mod add_image_tags;
+pub mod fetch_images_tags;
+pub mod fetch_tags;
+pub mod remove_image_tags;
+
+#[derive(FromQueryResult)]
+struct TagWithCount {
+    name: String,
+    image_count: i64,
+}
+
+impl From<TagWithCount> for Tag {
+    fn from(value: TagWithCount) -> Self {
+        Self {
+            name: value.name,
+            image_count: value.image_count as u64,
+        }
+    }
+}
+
+async fn fetch_image_model(
+    db: &impl ConnectionTrait,
+    file_name: &str,
+) -> Result<Option<images::Model>, DbErr> {
+    Images::find()
+        .filter(images::Column::FileName.eq(file_name))
+        .one(db)
+        .await
+}
+
+/// Adds the tags to the image, creating any tags that don't exist yet.
+pub(super) async fn insert_image_tags(
+    db: &impl ConnectionTrait,
+    image_id: i32,
+    tag_names: &[String],
+) -> Result<(), DbErr> {
+    let models = tag_names.iter().map(|name| tags::ActiveModel {
+        name: ActiveValue::Set(name.clone()),
+        ..Default::default()
+    });
+    Tags::insert_many(models)
+        .on_conflict(
+            OnConflict::column(tags::Column::Name)
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+
+    let tags = Tags::find()
+        .filter(tags::Column::Name.is_in(tag_names.iter().cloned()))
+        .all(db)
+        .await?;
+    let models = tags.into_iter().map(|t| image_tags::ActiveModel {
+        image_id: ActiveValue::Set(image_id),
+        tag_id: ActiveValue::Set(t.id),
+    });
+    ImageTags::insert_many(models)
+        .on_conflict(
+            OnConflict::columns([image_tags::Column::ImageId, image_tags::Column::TagId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
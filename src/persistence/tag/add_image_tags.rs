@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use sea_orm::{DbErr, TransactionError, TransactionTrait};
+
+use crate::{
+    domain::actions::tag::{AddImageTags, ImageTagsError},
+    persistence::{
+        tag::{fetch_image_model, insert_image_tags},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl AddImageTags for PersistenceManager {
+    async fn add_image_tags(
+        &self,
+        file_name: &str,
+        tag_names: &[String],
+    ) -> Result<(), ImageTagsError> {
+        let image = fetch_image_model(&self.db_conn, file_name)
+            .await
+            .map_err(|e| ImageTagsError::Persistence(e.to_string()))?
+            .ok_or(ImageTagsError::ImageNotFound)?;
+        if tag_names.is_empty() {
+            return Ok(());
+        }
+
+        let tag_names = tag_names.to_vec();
+        self.db_conn
+            .transaction::<_, (), DbErr>(|txn| {
+                Box::pin(async move { insert_image_tags(txn, image.id, &tag_names).await })
+            })
+            .await
+            .map_err(|e: TransactionError<DbErr>| ImageTagsError::Persistence(e.to_string()))?;
+
+        Ok(())
+    }
+}
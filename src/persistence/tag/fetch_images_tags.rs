@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sea_orm::{
+    ColumnTrait, EntityTrait, FromQueryResult, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait,
+};
+
+use crate::{
+    domain::actions::tag::FetchImagesTags,
+    persistence::{
+        entities::{image_tags, images, prelude::ImageTags, tags},
+        PersistenceManager,
+    },
+};
+
+#[derive(FromQueryResult)]
+struct ImageTag {
+    file_name: String,
+    name: String,
+}
+
+#[async_trait]
+impl FetchImagesTags for PersistenceManager {
+    async fn fetch_images_tags(
+        &self,
+        file_names: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, String> {
+        if file_names.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = ImageTags::find()
+            .select_only()
+            .column(images::Column::FileName)
+            .column(tags::Column::Name)
+            .join(JoinType::InnerJoin, image_tags::Relation::Images.def())
+            .join(JoinType::InnerJoin, image_tags::Relation::Tags.def())
+            .filter(images::Column::FileName.is_in(file_names.iter().cloned()))
+            .order_by_asc(tags::Column::Name)
+            .into_model::<ImageTag>()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut tags: HashMap<_, Vec<_>> = HashMap::new();
+        for row in rows {
+            tags.entry(row.file_name).or_default().push(row.name);
+        }
+
+        Ok(tags)
+    }
+}
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use sea_orm::{
+    ColumnTrait, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+};
+
+use crate::{
+    domain::{actions::tag::FetchTags, models::Tag},
+    persistence::{
+        entities::{image_tags, images, prelude::Tags, tags},
+        tag::TagWithCount,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchTags for PersistenceManager {
+    async fn fetch_tags(&self) -> Result<Vec<Tag>, String> {
+        // The inner joins skip tags left without images after removals or deletes. Images pending
+        // approval aren't counted, so tags only on those are skipped too.
+        let tags: Vec<_> = Tags::find()
+            .select_only()
+            .column(tags::Column::Name)
+            .column_as(image_tags::Column::ImageId.count(), "image_count")
+            .join(JoinType::InnerJoin, tags::Relation::ImageTags.def())
+            .join(JoinType::InnerJoin, image_tags::Relation::Images.def())
+            .filter(images::Column::PendingApproval.eq(false))
+            .group_by(tags::Column::Id)
+            .order_by_asc(tags::Column::Name)
+            .into_model::<TagWithCount>()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|m| m.into())
+            .collect();
+
+        Ok(tags)
+    }
+}
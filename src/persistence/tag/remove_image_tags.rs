@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use sea_orm::{sea_query::Query, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    domain::actions::tag::{ImageTagsError, RemoveImageTags},
+    persistence::{
+        entities::{image_tags, prelude::ImageTags, tags},
+        tag::fetch_image_model,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl RemoveImageTags for PersistenceManager {
+    async fn remove_image_tags(
+        &self,
+        file_name: &str,
+        tag_names: &[String],
+    ) -> Result<(), ImageTagsError> {
+        let image = fetch_image_model(&self.db_conn, file_name)
+            .await
+            .map_err(|e| ImageTagsError::Persistence(e.to_string()))?
+            .ok_or(ImageTagsError::ImageNotFound)?;
+
+        ImageTags::delete_many()
+            .filter(image_tags::Column::ImageId.eq(image.id))
+            .filter(
+                image_tags::Column::TagId.in_subquery(
+                    Query::select()
+                        .column(tags::Column::Id)
+                        .from(tags::Entity)
+                        .and_where(tags::Column::Name.is_in(tag_names.iter().cloned()))
+                        .to_owned(),
+                ),
+            )
+            .exec(&self.db_conn)
+            .await
+            .map_err(|e| ImageTagsError::Persistence(e.to_string()))?;
+
+        Ok(())
+    }
+}
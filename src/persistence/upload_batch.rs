@@ -0,0 +1,58 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter,
+};
+
+use crate::{
+    domain::models::UploadBatch,
+    persistence::entities::{prelude::UploadBatches, upload_batches},
+};
+
+pub mod delete_upload_batch;
+pub mod fetch_upload_batch_images;
+pub mod fetch_upload_batches;
+
+#[derive(FromQueryResult)]
+struct UploadBatchWithCount {
+    name: String,
+    created_at: chrono::NaiveDateTime,
+    image_count: i64,
+}
+
+impl From<UploadBatchWithCount> for UploadBatch {
+    fn from(value: UploadBatchWithCount) -> Self {
+        Self {
+            name: value.name,
+            created_at: value.created_at,
+            image_count: value.image_count as u64,
+        }
+    }
+}
+
+/// Returns the id of the batch, creating it if it doesn't exist yet.
+pub(super) async fn upsert_upload_batch(
+    db: &impl ConnectionTrait,
+    name: &str,
+) -> Result<i32, DbErr> {
+    UploadBatches::insert(upload_batches::ActiveModel {
+        name: ActiveValue::Set(name.to_string()),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(upload_batches::Column::Name)
+            .do_nothing()
+            .to_owned(),
+    )
+    .do_nothing()
+    .exec(db)
+    .await?;
+
+    UploadBatches::find()
+        .filter(upload_batches::Column::Name.eq(name))
+        .one(db)
+        .await?
+        .map(|m| m.id)
+        .ok_or_else(|| DbErr::RecordNotFound(format!("upload batch {}", name)))
+}
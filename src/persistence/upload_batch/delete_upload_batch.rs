@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter, TransactionTrait};
+
+use crate::{
+    domain::actions::upload_batch::DeleteUploadBatch,
+    persistence::{
+        entities::{images, prelude::Images, prelude::UploadBatches, upload_batches},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl DeleteUploadBatch for PersistenceManager {
+    async fn delete_upload_batch(&self, name: &str, file_names: &[String]) -> Result<(), String> {
+        let name = name.to_string();
+        let file_names = file_names.to_vec();
+        self.db_conn
+            .transaction::<_, (), DbErr>(|txn| {
+                Box::pin(async move {
+                    let Some(batch) = UploadBatches::find()
+                        .filter(upload_batches::Column::Name.eq(&name))
+                        .one(txn)
+                        .await?
+                    else {
+                        return Ok(());
+                    };
+
+                    Images::delete_many()
+                        .filter(images::Column::UploadBatchId.eq(batch.id))
+                        .filter(images::Column::FileName.is_in(file_names))
+                        .exec(txn)
+                        .await?;
+
+                    let left = Images::find()
+                        .filter(images::Column::UploadBatchId.eq(batch.id))
+                        .count(txn)
+                        .await?;
+                    if left == 0 {
+                        UploadBatches::delete_by_id(batch.id).exec(txn).await?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
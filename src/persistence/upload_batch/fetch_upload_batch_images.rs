@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QuerySelect};
+
+use crate::{
+    domain::actions::upload_batch::FetchUploadBatchImages,
+    persistence::{
+        entities::{images, prelude::Images, prelude::UploadBatches, upload_batches},
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchUploadBatchImages for PersistenceManager {
+    async fn fetch_upload_batch_images(&self, name: &str) -> Result<Option<Vec<String>>, String> {
+        let Some(batch) = UploadBatches::find()
+            .filter(upload_batches::Column::Name.eq(name))
+            .one(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+        else {
+            return Ok(None);
+        };
+
+        let file_names = batch
+            .find_related(Images)
+            .select_only()
+            .column(images::Column::FileName)
+            .into_tuple()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(Some(file_names))
+    }
+}
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, JoinType, QueryOrder, QuerySelect, RelationTrait};
+
+use crate::{
+    domain::{actions::upload_batch::FetchUploadBatches, models::UploadBatch},
+    persistence::{
+        entities::{images, prelude::UploadBatches, upload_batches},
+        upload_batch::UploadBatchWithCount,
+        PersistenceManager,
+    },
+};
+
+#[async_trait]
+impl FetchUploadBatches for PersistenceManager {
+    async fn fetch_upload_batches(&self, limit: u64) -> Result<Vec<UploadBatch>, String> {
+        let batches = UploadBatches::find()
+            .select_only()
+            .column(upload_batches::Column::Name)
+            .column(upload_batches::Column::CreatedAt)
+            .column_as(images::Column::Id.count(), "image_count")
+            .join(JoinType::LeftJoin, upload_batches::Relation::Images.def())
+            .group_by(upload_batches::Column::Id)
+            .order_by_desc(upload_batches::Column::CreatedAt)
+            .order_by_desc(upload_batches::Column::Id)
+            .limit(limit)
+            .into_model::<UploadBatchWithCount>()
+            .all(&self.db_conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|m| m.into())
+            .collect();
+
+        Ok(batches)
+    }
+}
@@ -1,2 +1,4 @@
+pub mod image_cache;
+mod screensaver_devices;
 pub mod screensaver_manager;
 mod screensaver_state;
@@ -0,0 +1,266 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// A disk cache of generated images, evicting the least recently used ones when the total size
+/// of the cached files exceeds the budget.
+#[derive(Clone)]
+pub struct ImageCache {
+    dir: PathBuf,
+    budget: u64,
+    index: Arc<Mutex<CacheIndex>>,
+}
+
+impl ImageCache {
+    /// Opens the cache in `dir`, indexing the files already in it from oldest to newest.
+    pub fn open(dir: impl Into<PathBuf>, budget: u64) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let Some(key) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if !metadata.is_file() || key.starts_with('.') {
+                // Leftover temporary files of interrupted writes.
+                let _ = fs::remove_file(entry.path());
+                continue;
+            }
+            files.push((metadata.modified()?, key, metadata.len()));
+        }
+        files.sort();
+
+        let mut index = CacheIndex::default();
+        for (_, key, size) in files {
+            index.touch(key, size);
+        }
+
+        let cache = Self {
+            dir,
+            budget,
+            index: Arc::new(Mutex::new(index)),
+        };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// Returns the cached file, marking it as recently used.
+    pub fn get(&self, key: &str) -> Option<CachedFile> {
+        let mut index = self.acquire_lock();
+        let size = index.entries.get(key)?.size;
+        index.touch(key.to_string(), size);
+        Some(self.pin(&mut index, key))
+    }
+
+    /// Caches `bytes` under `key`, evicting the least recently used files if over budget.
+    /// Returns the cached file.
+    pub fn insert(&self, key: &str, bytes: &[u8]) -> io::Result<CachedFile> {
+        let path = self.dir.join(key);
+        // Written to a temporary file first, so a file is never served half written.
+        let tmp_path = self
+            .dir
+            .join(format!(".{:016x}.tmp", rand::random::<u64>()));
+        let res = fs::write(&tmp_path, bytes).and_then(|_| fs::rename(&tmp_path, &path));
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        res?;
+
+        let file = {
+            let mut index = self.acquire_lock();
+            index.touch(key.to_string(), bytes.len() as u64);
+            self.pin(&mut index, key)
+        };
+        self.evict();
+        Ok(file)
+    }
+
+    /// Pins the entry, so it isn't evicted until the returned [CachedFile] is dropped.
+    fn pin(&self, index: &mut CacheIndex, key: &str) -> CachedFile {
+        if let Some(entry) = index.entries.get_mut(key) {
+            entry.pins += 1;
+        }
+        CachedFile {
+            path: self.dir.join(key),
+            key: key.to_string(),
+            cache: self.clone(),
+        }
+    }
+
+    /// Removes the least recently used files that aren't pinned until the cache fits its budget.
+    fn evict(&self) {
+        let mut index = self.acquire_lock();
+        while index.total_size > self.budget {
+            let Some(key) = index.least_recently_used() else {
+                break;
+            };
+            index.remove(&key);
+            if let Err(e) = fs::remove_file(self.dir.join(&key)) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    tracing::warn!("failed to evict {} from the image cache: {}", key, e);
+                }
+            }
+        }
+    }
+
+    /// In this case, we don't care if the mutex is poisoned, as the index is only an estimate of
+    /// the files on disk.
+    fn acquire_lock(&self) -> MutexGuard<'_, CacheIndex> {
+        match self.index.lock() {
+            Ok(guard) => guard,
+            Err(poison) => {
+                tracing::debug!("Accessing poisoned mutex");
+                poison.into_inner()
+            }
+        }
+    }
+}
+
+/// A file in an [ImageCache], which isn't evicted while this is held.
+pub struct CachedFile {
+    path: PathBuf,
+    key: String,
+    cache: ImageCache,
+}
+
+impl CachedFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for CachedFile {
+    fn drop(&mut self) {
+        if let Some(entry) = self.cache.acquire_lock().entries.get_mut(&self.key) {
+            entry.pins -= 1;
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheIndex {
+    entries: HashMap<String, CacheEntry>,
+    /// The keys of `entries`, ordered by when they were last used.
+    recency: BTreeMap<u64, String>,
+    total_size: u64,
+    tick: u64,
+}
+
+struct CacheEntry {
+    size: u64,
+    last_used: u64,
+    /// The number of [CachedFile]s held for the entry.
+    pins: usize,
+}
+
+impl CacheIndex {
+    /// Inserts or updates the entry, marking it as the most recently used.
+    fn touch(&mut self, key: String, size: u64) {
+        let pins = self.entries.get(&key).map_or(0, |e| e.pins);
+        self.remove(&key);
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                size,
+                last_used: self.tick,
+                pins,
+            },
+        );
+        self.total_size += size;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.total_size -= entry.size;
+        }
+    }
+
+    /// Returns the least recently used entry that isn't pinned.
+    fn least_recently_used(&self) -> Option<String> {
+        self.recency
+            .values()
+            .find(|key| self.entries.get(*key).is_some_and(|e| e.pins == 0))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_skips_touched_entries() {
+        // Arrange
+        let mut sut = CacheIndex::default();
+        sut.touch("a".to_string(), 1);
+        sut.touch("b".to_string(), 1);
+
+        // Act
+        sut.touch("a".to_string(), 1);
+
+        // Assert
+        assert_eq!(Some("b".to_string()), sut.least_recently_used());
+        assert_eq!(2, sut.total_size);
+    }
+
+    #[test]
+    fn least_recently_used_skips_pinned_entry() {
+        // Arrange
+        let mut sut = CacheIndex::default();
+        sut.touch("a".to_string(), 1);
+        sut.touch("b".to_string(), 1);
+        sut.entries.get_mut("a").expect("a should be cached").pins += 1;
+
+        // Act
+        let res = sut.least_recently_used();
+
+        // Assert
+        assert_eq!(Some("b".to_string()), res);
+    }
+
+    #[test]
+    fn remove_updates_total_size() {
+        // Arrange
+        let mut sut = CacheIndex::default();
+        sut.touch("a".to_string(), 3);
+        sut.touch("b".to_string(), 5);
+
+        // Act
+        sut.remove("a");
+
+        // Assert
+        assert_eq!(5, sut.total_size);
+        assert_eq!(Some("b".to_string()), sut.least_recently_used());
+    }
+
+    #[test]
+    fn evict_skips_held_files() {
+        // Arrange
+        let dir =
+            std::env::temp_dir().join(format!("image_cache_test_{:016x}", rand::random::<u64>()));
+        let sut = ImageCache::open(&dir, 1).expect("cache should open");
+        let held = sut.insert("a", b"aa").expect("a should be cached");
+        drop(sut.insert("b", b"bb").expect("b should be cached"));
+
+        // Act
+        let res = sut.insert("c", b"cc");
+
+        // Assert
+        assert!(res.is_ok());
+        assert!(held.path().exists());
+        assert!(!dir.join("b").exists());
+
+        drop(held);
+        drop(res);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
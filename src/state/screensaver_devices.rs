@@ -0,0 +1,578 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    domain::{
+        models::{Image, ScreensaverCursor, ScreensaverRotation},
+        screensaver::{DeviceScreensaver, ResolveState, Screensaver},
+    },
+    state::screensaver_state::ScreensaverState,
+};
+
+/// The most device rotations kept, beyond which the least recently resolved one is evicted.
+const MAX_DEVICES: usize = 100;
+
+// Invariants:
+// - Every rotation holds the same images, only their order and current index differ.
+//   - Modifications through [Screensaver] are applied to every rotation.
+//   - New rotations are forked from `default`, which always exists.
+// - A device without a rotation is shown the current image of `default`.
+//   - Its rotation is only created once it resolves that image.
+// - There are never more than `MAX_DEVICES` device rotations.
+pub struct ScreensaverDevices {
+    /// The rotation used when no device is given.
+    default: ScreensaverState,
+    /// The rotations of each device, keyed by device id.
+    devices: HashMap<String, ScreensaverState>,
+    /// The value of `resolves` when each device last resolved an image.
+    /// Devices that haven't resolved an image since being restored are missing.
+    last_resolved: HashMap<String, u64>,
+    /// The number of images resolved by devices.
+    resolves: u64,
+    /// The rotations whose order changed since the last call to `take_changed_rotations`.
+    changed: HashSet<Option<String>>,
+    /// The rotations whose current index, but not their order, changed since the last call to
+    /// `take_moved_cursors`.
+    moved: HashSet<Option<String>>,
+    /// The devices evicted since the last call to `take_removed_devices`.
+    removed: HashSet<String>,
+}
+
+impl ScreensaverDevices {
+    /// Create a [ScreensaverDevices] from saved rotations holding the given `Image`s.
+    /// The key should be the file name of the image the key refers to.
+    pub fn restore(rotations: Vec<ScreensaverRotation>, values: HashMap<String, Image>) -> Self {
+        let mut default = None;
+        let mut devices = HashMap::new();
+        for rotation in rotations {
+            let state = ScreensaverState::restore(
+                rotation.file_names,
+                rotation.current_index,
+                values.clone(),
+            );
+            match rotation.device_id {
+                None => default = Some(state),
+                Some(device_id) => {
+                    devices.insert(device_id, state);
+                }
+            }
+        }
+        let default = default.unwrap_or_else(|| {
+            let mut state = ScreensaverState::new();
+            state.replace(values);
+            state
+        });
+
+        Self {
+            default,
+            devices,
+            last_resolved: HashMap::new(),
+            resolves: 0,
+            changed: HashSet::new(),
+            moved: HashSet::new(),
+            removed: HashSet::new(),
+        }
+    }
+
+    /// Returns a [ScreensaverRotation] for every rotation that changed since the last call.
+    /// Their cursors are saved along with them, so they no longer count as moved.
+    pub fn take_changed_rotations(&mut self) -> Vec<ScreensaverRotation> {
+        let changed: Vec<_> = self.changed.drain().collect();
+        changed
+            .into_iter()
+            .filter_map(|device_id| {
+                self.moved.remove(&device_id);
+                self.rotation(device_id.as_deref())
+                    .map(|s| s.to_rotation(device_id))
+            })
+            .collect()
+    }
+
+    /// Marks the rotations of the given devices as changed, e.g. when saving them failed.
+    pub fn mark_changed(&mut self, device_ids: impl IntoIterator<Item = Option<String>>) {
+        self.changed.extend(device_ids);
+    }
+
+    /// Returns a [ScreensaverCursor] for every rotation that only moved since the last call.
+    pub fn take_moved_cursors(&mut self) -> Vec<ScreensaverCursor> {
+        let moved: Vec<_> = self.moved.drain().collect();
+        moved
+            .into_iter()
+            .filter_map(|device_id| {
+                self.rotation(device_id.as_deref())
+                    .map(|s| ScreensaverCursor {
+                        current_index: s.current_index(),
+                        device_id,
+                    })
+            })
+            .collect()
+    }
+
+    /// Marks the rotations of the given devices as moved, e.g. when saving their cursors failed.
+    pub fn mark_moved(&mut self, device_ids: impl IntoIterator<Item = Option<String>>) {
+        self.moved.extend(device_ids);
+    }
+
+    /// Returns the ids of the devices evicted since the last call.
+    pub fn take_removed_devices(&mut self) -> Vec<String> {
+        self.removed.drain().collect()
+    }
+
+    /// Marks the given devices as evicted, e.g. when deleting their rotations failed.
+    /// Devices that got a new rotation in the meantime are ignored.
+    pub fn mark_removed(&mut self, device_ids: impl IntoIterator<Item = String>) {
+        for device_id in device_ids {
+            if !self.devices.contains_key(&device_id) {
+                self.removed.insert(device_id);
+            }
+        }
+    }
+
+    fn rotation(&self, device_id: Option<&str>) -> Option<&ScreensaverState> {
+        match device_id {
+            None => Some(&self.default),
+            Some(id) => self.devices.get(id),
+        }
+    }
+
+    /// Marks a rotation that resolved an image as moved, or as changed when resolving started
+    /// a new order.
+    fn mark_resolved(&mut self, device_id: Option<&str>) {
+        let device_id = device_id.map(str::to_string);
+        let reshuffled = self
+            .rotation(device_id.as_deref())
+            .is_some_and(|s| s.current_index() == Some(0));
+        if reshuffled {
+            self.changed.insert(device_id);
+        } else {
+            self.moved.insert(device_id);
+        }
+    }
+
+    fn mark_all_changed(&mut self) {
+        self.changed.insert(None);
+        self.changed.extend(self.devices.keys().cloned().map(Some));
+    }
+
+    /// Creates the rotation for the device, evicting the least recently resolved devices
+    /// if there are too many.
+    fn create_rotation(&mut self, device_id: &str) {
+        while self.devices.len() >= MAX_DEVICES {
+            let evicted = match self
+                .devices
+                .keys()
+                .min_by_key(|id| self.last_resolved.get(*id).copied().unwrap_or(0))
+            {
+                Some(id) => id.clone(),
+                None => break,
+            };
+            tracing::debug!("evicting rotation for device {}", evicted);
+            self.devices.remove(&evicted);
+            self.last_resolved.remove(&evicted);
+            self.changed.remove(&Some(evicted.clone()));
+            self.moved.remove(&Some(evicted.clone()));
+            self.removed.insert(evicted);
+        }
+
+        tracing::debug!("creating rotation for device {}", device_id);
+        self.devices
+            .insert(device_id.to_string(), self.default.fork());
+        self.changed.insert(Some(device_id.to_string()));
+        self.removed.remove(device_id);
+    }
+}
+
+impl DeviceScreensaver for ScreensaverDevices {
+    fn current_for_device(&self, device_id: Option<&str>) -> Option<Image> {
+        device_id
+            .and_then(|id| self.devices.get(id))
+            .unwrap_or(&self.default)
+            .current()
+    }
+
+    fn resolve_for_device(&mut self, device_id: Option<&str>, file_name: &str) -> ResolveState {
+        let device_id = match device_id {
+            Some(device_id) => device_id,
+            None => {
+                let res = self.default.resolve(file_name);
+                if res == ResolveState::Resolved {
+                    self.mark_resolved(None);
+                }
+                return res;
+            }
+        };
+
+        if !self.devices.contains_key(device_id) {
+            match self.default.current() {
+                None => return ResolveState::NoImages,
+                Some(curr) if curr.file_name != file_name => return ResolveState::NotCurrent,
+                Some(_) => self.create_rotation(device_id),
+            }
+        }
+
+        let res = self
+            .devices
+            .get_mut(device_id)
+            .expect("rotation should exist since it was just created")
+            .resolve(file_name);
+        if res == ResolveState::Resolved {
+            self.resolves += 1;
+            self.last_resolved
+                .insert(device_id.to_string(), self.resolves);
+            self.mark_resolved(Some(device_id));
+        }
+        res
+    }
+}
+
+// The result of modifying `default` is returned. Since every rotation holds the same images,
+// the other rotations always have the same result and theirs are ignored.
+impl Screensaver for ScreensaverDevices {
+    fn current(&self) -> Option<Image> {
+        self.default.current()
+    }
+
+    fn resolve(&mut self, file_name: &str) -> ResolveState {
+        self.resolve_for_device(None, file_name)
+    }
+
+    fn insert(&mut self, value: Image) -> Result<(), ()> {
+        self.default.insert(value.clone())?;
+        for state in self.devices.values_mut() {
+            let _ = state.insert(value.clone());
+        }
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    fn insert_many(&mut self, values: HashMap<String, Image>) -> Result<(), Vec<String>> {
+        self.default.insert_many(values.clone())?;
+        for state in self.devices.values_mut() {
+            let _ = state.insert_many(values.clone());
+        }
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    fn rename_image(&mut self, old_name: &str, new_name: &str) -> Result<(), ()> {
+        self.default.rename_image(old_name, new_name)?;
+        for state in self.devices.values_mut() {
+            let _ = state.rename_image(old_name, new_name);
+        }
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    fn delete_image(&mut self, file_name: &str) -> Result<(), ()> {
+        self.default.delete_image(file_name)?;
+        for state in self.devices.values_mut() {
+            let _ = state.delete_image(file_name);
+        }
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.default.clear();
+        for state in self.devices.values_mut() {
+            state.clear();
+        }
+        self.mark_all_changed();
+    }
+
+    fn replace(&mut self, values: HashMap<String, Image>) {
+        for state in self.devices.values_mut() {
+            state.replace(values.clone());
+        }
+        self.default.replace(values);
+        self.mark_all_changed();
+    }
+
+    fn merge(&mut self, values: HashMap<String, Image>) {
+        for state in self.devices.values_mut() {
+            state.merge(values.clone());
+        }
+        self.default.merge(values);
+        self.mark_all_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, ops::Range};
+
+    use super::*;
+
+    fn mk_sut() -> ScreensaverDevices {
+        ScreensaverDevices::restore(Vec::new(), HashMap::new())
+    }
+
+    fn mk_img(x: u32) -> Image {
+        Image {
+            file_name: format!("test {}", x),
+            width: x,
+            height: x,
+        }
+    }
+
+    fn mk_imgs(r: Range<u32>) -> HashMap<String, Image> {
+        r.map(|i| {
+            let img = mk_img(i);
+            (img.file_name.clone(), img)
+        })
+        .collect()
+    }
+
+    /// Resolves through a full iteration of the device's rotation, collecting every image name.
+    fn collect_names(sut: &mut ScreensaverDevices, device_id: Option<&str>) -> HashSet<String> {
+        let mut set = HashSet::new();
+        loop {
+            let curr_name = sut
+                .current_for_device(device_id)
+                .expect("sut should contain images")
+                .file_name;
+            if set.contains(&curr_name) {
+                break;
+            }
+            sut.resolve_for_device(device_id, &curr_name);
+            set.insert(curr_name);
+        }
+        set
+    }
+
+    /// Creates the rotation of the device by resolving the image it is shown.
+    fn create_device(sut: &mut ScreensaverDevices, device_id: &str) {
+        let curr = sut
+            .current_for_device(Some(device_id))
+            .expect("sut should contain images");
+        let res = sut.resolve_for_device(Some(device_id), &curr.file_name);
+        assert_eq!(res, ResolveState::Resolved);
+    }
+
+    #[test]
+    fn resolving_one_device_does_not_change_another() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        create_device(&mut sut, "a");
+        create_device(&mut sut, "b");
+        let a = sut
+            .current_for_device(Some("a"))
+            .expect("sut should contain images");
+        let b = sut
+            .current_for_device(Some("b"))
+            .expect("sut should contain images");
+
+        // Act
+        let res = sut.resolve_for_device(Some("a"), &a.file_name);
+
+        // Assert
+        assert_eq!(res, ResolveState::Resolved);
+
+        let new_b = sut
+            .current_for_device(Some("b"))
+            .expect("sut should contain images");
+        assert_eq!(b, new_b);
+    }
+
+    #[test]
+    fn new_device_contains_existing_images() {
+        // Arrange
+        let mut sut = mk_sut();
+        let imgs = mk_imgs(1..11);
+        let names: HashSet<_> = imgs.keys().cloned().collect();
+
+        // Act
+        sut.replace(imgs);
+
+        // Assert
+        assert_eq!(names, collect_names(&mut sut, Some("new")));
+    }
+
+    #[test]
+    fn insert_reaches_every_device() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        create_device(&mut sut, "a");
+        let img = mk_img(11);
+        let name = img.file_name.clone();
+
+        // Act
+        let res = sut.insert(img);
+
+        // Assert
+        assert!(res.is_ok());
+        assert!(collect_names(&mut sut, None).contains(&name));
+        assert!(collect_names(&mut sut, Some("a")).contains(&name));
+    }
+
+    #[test]
+    fn restore_keeps_each_device_position() {
+        // Arrange
+        let mut saved = mk_sut();
+        saved.replace(mk_imgs(1..11));
+        create_device(&mut saved, "a");
+        create_device(&mut saved, "b");
+        let b = saved
+            .current_for_device(Some("b"))
+            .expect("saved should contain images");
+        saved.resolve_for_device(Some("b"), &b.file_name);
+        let rotations = saved.take_changed_rotations();
+
+        // Act
+        let sut = ScreensaverDevices::restore(rotations, mk_imgs(1..11));
+
+        // Assert
+        assert_eq!(
+            saved.current_for_device(Some("a")),
+            sut.current_for_device(Some("a"))
+        );
+        assert_eq!(
+            saved.current_for_device(Some("b")),
+            sut.current_for_device(Some("b"))
+        );
+    }
+
+    #[test]
+    fn delete_reaches_every_device() {
+        // Arrange
+        let mut sut = mk_sut();
+        let imgs = mk_imgs(1..11);
+        let name = imgs
+            .keys()
+            .next()
+            .expect("imgs should not be empty")
+            .clone();
+        sut.replace(imgs);
+        create_device(&mut sut, "a");
+
+        // Act
+        let res = sut.delete_image(&name);
+
+        // Assert
+        assert!(res.is_ok());
+        assert!(!collect_names(&mut sut, None).contains(&name));
+        assert!(!collect_names(&mut sut, Some("a")).contains(&name));
+    }
+
+    #[test]
+    fn current_for_unknown_device_does_not_create_rotation() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        sut.take_changed_rotations();
+
+        // Act
+        let res = sut.current_for_device(Some("new"));
+
+        // Assert
+        assert_eq!(res, sut.current());
+        assert!(sut.take_changed_rotations().is_empty());
+    }
+
+    #[test]
+    fn new_device_resolves_default_current() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        let curr = sut.current().expect("sut should contain images");
+
+        // Act
+        let res = sut.resolve_for_device(Some("new"), &curr.file_name);
+
+        // Assert
+        assert_eq!(res, ResolveState::Resolved);
+        assert_eq!(sut.current(), Some(curr));
+        assert_ne!(sut.current(), sut.current_for_device(Some("new")));
+    }
+
+    #[test]
+    fn resolving_not_current_does_not_create_rotation() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        sut.take_changed_rotations();
+
+        // Act
+        let res = sut.resolve_for_device(Some("new"), "not current");
+
+        // Assert
+        assert_eq!(res, ResolveState::NotCurrent);
+        assert!(sut.take_changed_rotations().is_empty());
+    }
+
+    #[test]
+    fn too_many_devices_evicts_least_recently_resolved() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        for i in 0..MAX_DEVICES {
+            create_device(&mut sut, &i.to_string());
+        }
+        create_device(&mut sut, "0");
+
+        // Act
+        create_device(&mut sut, "new");
+
+        // Assert
+        assert_eq!(sut.take_removed_devices(), vec!["1".to_string()]);
+        assert_eq!(sut.devices.len(), MAX_DEVICES);
+        assert!(sut.devices.contains_key("0"));
+        assert!(sut.devices.contains_key("new"));
+    }
+
+    #[test]
+    fn resolving_saves_only_the_cursor() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        create_device(&mut sut, "device");
+        sut.take_changed_rotations();
+        let curr = sut.current_for_device(Some("device")).unwrap();
+
+        // Act
+        sut.resolve_for_device(Some("device"), &curr.file_name);
+
+        // Assert
+        assert!(sut.take_changed_rotations().is_empty());
+        let cursors = sut.take_moved_cursors();
+        assert_eq!(cursors.len(), 1);
+        assert_eq!(cursors[0].device_id.as_deref(), Some("device"));
+        assert_eq!(cursors[0].current_index, Some(2));
+    }
+
+    #[test]
+    fn new_device_saves_its_rotation() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        sut.take_changed_rotations();
+
+        // Act
+        create_device(&mut sut, "device");
+
+        // Assert
+        let rotations = sut.take_changed_rotations();
+        assert_eq!(rotations.len(), 1);
+        assert_eq!(rotations[0].device_id.as_deref(), Some("device"));
+        assert!(sut.take_moved_cursors().is_empty());
+    }
+
+    #[test]
+    fn many_inserts_save_each_rotation_once() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        create_device(&mut sut, "device");
+        sut.take_changed_rotations();
+
+        // Act
+        for i in 11..21 {
+            sut.insert(mk_img(i)).unwrap();
+        }
+
+        // Assert
+        assert_eq!(sut.take_changed_rotations().len(), 2);
+        assert!(sut.take_moved_cursors().is_empty());
+    }
+}
@@ -1,30 +1,92 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
 };
 
+use tokio::sync::Notify;
+
 use crate::{
     domain::{
-        models::Image,
-        screensaver::{ResolveState, Screensaver},
+        actions::screensaver::{
+            DeleteScreensaverRotations, SaveScreensaverCursors, SaveScreensaverRotations,
+        },
+        models::{Image, ScreensaverRotation},
+        screensaver::{DeviceScreensaver, ResolveState, Screensaver},
     },
-    state::screensaver_state::ScreensaverState,
+    state::screensaver_devices::ScreensaverDevices,
 };
 
+/// How long to wait after a change before saving, so that a burst of changes,
+/// e.g. an upload of many images, rewrites every rotation only once.
+const PERSIST_DELAY: Duration = Duration::from_secs(2);
+
 #[derive(Clone)]
 pub struct ScreensaverManager {
-    state: Arc<Mutex<ScreensaverState>>,
+    state: Arc<Mutex<ScreensaverDevices>>,
+    /// Notified whenever `state` may have changed, so that it can be saved.
+    changed: Arc<Notify>,
 }
 
 impl ScreensaverManager {
-    pub fn new() -> Self {
+    /// Create a [ScreensaverManager] from saved rotations holding the given `Image`s.
+    /// The key should be the file name of the image the key refers to.
+    pub fn restore(rotations: Vec<ScreensaverRotation>, values: HashMap<String, Image>) -> Self {
         Self {
-            state: Arc::new(Mutex::new(ScreensaverState::new())),
+            state: Arc::new(Mutex::new(ScreensaverDevices::restore(rotations, values))),
+            changed: Arc::new(Notify::new()),
         }
     }
 
+    /// Spawns a task that saves changed rotations with `sr` whenever they change,
+    /// and deletes the rotations of evicted devices.
+    /// Rotations that only moved to another image have only their cursor saved.
+    pub fn persist_with(
+        &self,
+        sr: impl 'static
+            + Send
+            + Sync
+            + SaveScreensaverRotations
+            + SaveScreensaverCursors
+            + DeleteScreensaverRotations,
+    ) {
+        let mngr = self.clone();
+        tokio::spawn(async move {
+            loop {
+                mngr.changed.notified().await;
+                tokio::time::sleep(PERSIST_DELAY).await;
+                // Removed devices are deleted first, so a device that got a new rotation
+                // since being evicted is saved afterwards.
+                let removed = mngr.acquire_lock().take_removed_devices();
+                if let Err(e) = sr.delete_screensaver_rotations(&removed).await {
+                    tracing::error!("failed to delete screensaver rotations: {}", e);
+                    mngr.acquire_lock().mark_removed(removed);
+                }
+
+                let rotations = mngr.acquire_lock().take_changed_rotations();
+                if !rotations.is_empty() {
+                    if let Err(e) = sr.save_screensaver_rotations(&rotations).await {
+                        tracing::error!("failed to save screensaver rotations: {}", e);
+                        // They will be saved along with the next change.
+                        mngr.acquire_lock()
+                            .mark_changed(rotations.into_iter().map(|r| r.device_id));
+                    }
+                }
+
+                let cursors = mngr.acquire_lock().take_moved_cursors();
+                if !cursors.is_empty() {
+                    if let Err(e) = sr.save_screensaver_cursors(&cursors).await {
+                        tracing::error!("failed to save screensaver cursors: {}", e);
+                        mngr.acquire_lock()
+                            .mark_moved(cursors.into_iter().map(|c| c.device_id));
+                    }
+                }
+            }
+        });
+    }
+
     /// In this case, we don't care if the mutex is poisoned, as we simply hold a list of values.
-    fn acquire_lock(&self) -> MutexGuard<'_, ScreensaverState> {
+    fn acquire_lock(&self) -> MutexGuard<'_, ScreensaverDevices> {
         match self.state.lock() {
             Ok(guard) => guard,
             Err(poison) => {
@@ -33,6 +95,23 @@ impl ScreensaverManager {
             }
         }
     }
+
+    /// Runs `f` with the lock held, then notifies that the state may have changed.
+    fn modify<T>(&self, f: impl FnOnce(&mut ScreensaverDevices) -> T) -> T {
+        let res = f(&mut self.acquire_lock());
+        self.changed.notify_one();
+        res
+    }
+}
+
+impl DeviceScreensaver for ScreensaverManager {
+    fn current_for_device(&self, device_id: Option<&str>) -> Option<Image> {
+        self.acquire_lock().current_for_device(device_id)
+    }
+
+    fn resolve_for_device(&mut self, device_id: Option<&str>, file_name: &str) -> ResolveState {
+        self.modify(|s| s.resolve_for_device(device_id, file_name))
+    }
 }
 
 impl Screensaver for ScreensaverManager {
@@ -41,30 +120,34 @@ impl Screensaver for ScreensaverManager {
     }
 
     fn resolve(&mut self, file_name: &str) -> ResolveState {
-        self.acquire_lock().resolve(file_name)
+        self.modify(|s| s.resolve(file_name))
     }
 
     fn insert(&mut self, value: Image) -> Result<(), ()> {
-        self.acquire_lock().insert(value)
+        self.modify(|s| s.insert(value))
     }
 
     fn insert_many(&mut self, values: HashMap<String, Image>) -> Result<(), Vec<String>> {
-        self.acquire_lock().insert_many(values)
+        self.modify(|s| s.insert_many(values))
     }
 
     fn rename_image(&mut self, old_name: &str, new_name: &str) -> Result<(), ()> {
-        self.acquire_lock().rename_image(old_name, new_name)
+        self.modify(|s| s.rename_image(old_name, new_name))
     }
 
     fn delete_image(&mut self, file_name: &str) -> Result<(), ()> {
-        self.acquire_lock().delete_image(file_name)
+        self.modify(|s| s.delete_image(file_name))
     }
 
     fn clear(&mut self) {
-        self.acquire_lock().clear()
+        self.modify(|s| s.clear())
     }
 
     fn replace(&mut self, values: HashMap<String, Image>) {
-        self.acquire_lock().replace(values)
+        self.modify(|s| s.replace(values))
+    }
+
+    fn merge(&mut self, values: HashMap<String, Image>) {
+        self.modify(|s| s.merge(values))
     }
 }
@@ -3,7 +3,7 @@ use std::collections::{HashMap, HashSet};
 use rand::{seq::SliceRandom, thread_rng, Rng, RngCore};
 
 use crate::domain::{
-    models::Image,
+    models::{Image, ScreensaverRotation},
     screensaver::{ResolveState, Screensaver},
 };
 
@@ -28,6 +28,75 @@ impl ScreensaverState {
         }
     }
 
+    /// Create a [ScreensaverState] with the same images as `self`, but in its own shuffled order.
+    /// The fork starts at the current image of `self`, so it can be resolved in the fork.
+    pub fn fork(&self) -> Self {
+        let mut state = Self::new();
+        state.replace(
+            self.images
+                .iter()
+                .map(|i| (i.file_name.clone(), i.clone()))
+                .collect(),
+        );
+        if let (Some(curr), Some(idx)) = (self.current(), state.current_index) {
+            if let Some(pos) = state
+                .images
+                .iter()
+                .position(|i| i.file_name == curr.file_name)
+            {
+                state.images.swap(idx, pos);
+            }
+        }
+        state
+    }
+
+    /// Create a [ScreensaverState] in the order of a saved rotation, then merge `values` into it.
+    /// Saved names that are duplicated or not in `values` are dropped,
+    /// so the invariants hold no matter what was saved.
+    pub fn restore(
+        file_names: Vec<String>,
+        current_index: Option<usize>,
+        values: HashMap<String, Image>,
+    ) -> Self {
+        let mut seen = HashSet::new();
+        // Only the file names are needed, `merge` replaces these with the given values.
+        let images: Vec<_> = file_names
+            .into_iter()
+            .filter(|n| seen.insert(n.clone()))
+            .map(|file_name| Image {
+                file_name,
+                width: 0,
+                height: 0,
+            })
+            .collect();
+        let current_index = if images.is_empty() {
+            None
+        } else {
+            Some(current_index.filter(|idx| *idx < images.len()).unwrap_or(0))
+        };
+
+        let mut state = Self {
+            images,
+            current_index,
+        };
+        state.merge(values);
+        state
+    }
+
+    /// Returns the index of the current image in the order of `self`.
+    pub fn current_index(&self) -> Option<usize> {
+        self.current_index
+    }
+
+    /// Create a [ScreensaverRotation] holding the order and position of `self`.
+    pub fn to_rotation(&self, device_id: Option<String>) -> ScreensaverRotation {
+        ScreensaverRotation {
+            device_id,
+            file_names: self.images.iter().map(|i| i.file_name.clone()).collect(),
+            current_index: self.current_index,
+        }
+    }
+
     /// This inserts a single image. This could be called multiple times to insert multiple images.
     fn insert_impl(&mut self, rng: &mut impl RngCore, value: Image) {
         self.images.push(value);
@@ -165,8 +234,7 @@ impl Screensaver for ScreensaverState {
     fn replace(&mut self, values: HashMap<String, Image>) {
         let values: Vec<_> = values.into_values().collect();
         if values.is_empty() {
-            self.current_index = None;
-            self.images = values;
+            self.clear();
         } else {
             let curr_name = self.current().map(|e| e.file_name);
             self.images = values;
@@ -178,6 +246,44 @@ impl Screensaver for ScreensaverState {
             }
         }
     }
+
+    fn merge(&mut self, mut values: HashMap<String, Image>) {
+        let curr_name = self.current().map(|e| e.file_name);
+        let mut new_idx = self.current_index;
+        let mut images = Vec::with_capacity(self.images.len());
+        for (idx, image) in self.images.drain(..).enumerate() {
+            match values.remove(&image.file_name) {
+                Some(value) => images.push(value),
+                // Removing an image before the current one shifts the current one down.
+                None => {
+                    if let Some(curr_idx) = new_idx {
+                        if idx < curr_idx {
+                            new_idx = Some(curr_idx - 1);
+                        }
+                    }
+                }
+            }
+        }
+        self.images = images;
+
+        let len = self.images.len();
+        if len == 0 {
+            self.current_index = None;
+        } else if new_idx.is_some_and(|idx| idx < len) {
+            self.current_index = new_idx;
+        } else {
+            let mut rng = thread_rng();
+            self.shuffle(&mut rng);
+            if let Some(curr_name) = curr_name {
+                ensure_different_next_image(&curr_name, &mut self.images, &mut rng);
+            }
+        }
+
+        let mut rng = thread_rng();
+        for value in values.into_values() {
+            self.insert_impl(&mut rng, value);
+        }
+    }
 }
 
 // This helps avoid a bug when making successive calls to resolve
@@ -822,4 +928,95 @@ mod tests {
         // Assert
         assert!(res.is_err());
     }
+
+    #[test]
+    fn current_is_same_after_merge() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        let curr = sut.current().expect("sut should have images");
+        sut.resolve(&curr.file_name);
+
+        // Act
+        let a = sut.current().expect("sut should have images");
+        sut.merge(mk_imgs(1..15));
+
+        // Assert
+        let b = sut.current().expect("sut should have images");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn contains_images_after_merge() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+        let imgs = mk_imgs(5..15);
+        let names: HashSet<_> = imgs.keys().cloned().collect();
+
+        // Act
+        sut.merge(imgs);
+
+        // Assert
+        let mut set = HashSet::new();
+        loop {
+            let curr_name = sut.current().expect("sut should contain images").file_name;
+            if set.contains(&curr_name) {
+                break;
+            }
+            sut.resolve(&curr_name);
+            set.insert(curr_name);
+        }
+
+        assert_eq!(names, set);
+    }
+
+    #[test]
+    fn current_is_none_after_empty_merge() {
+        // Arrange
+        let mut sut = mk_sut();
+        sut.replace(mk_imgs(1..11));
+
+        // Act
+        sut.merge(HashMap::new());
+
+        // Assert
+        assert!(sut.current().is_none());
+    }
+
+    #[test]
+    fn restore_keeps_saved_order_and_position() {
+        // Arrange
+        let mut saved = ScreensaverState::new();
+        saved.replace(mk_imgs(1..11));
+        let curr = saved.current().expect("saved should have images");
+        saved.resolve(&curr.file_name);
+        let rotation = saved.to_rotation(None);
+
+        // Act
+        let sut = ScreensaverState::restore(
+            rotation.file_names.clone(),
+            rotation.current_index,
+            mk_imgs(1..11),
+        );
+
+        // Assert
+        let restored = sut.to_rotation(None);
+        assert_eq!(rotation.file_names, restored.file_names);
+        assert_eq!(rotation.current_index, restored.current_index);
+        assert_eq!(saved.current(), sut.current());
+    }
+
+    #[test]
+    fn restore_drops_unknown_names_and_out_of_range_index() {
+        // Arrange
+        let file_names = vec!["unknown".to_string(), mk_img(1).file_name];
+
+        // Act
+        let sut = ScreensaverState::restore(file_names, Some(10), mk_imgs(1..2));
+
+        // Assert
+        let curr = sut.current().expect("sut should have images");
+        assert_eq!(curr, mk_img(1));
+    }
 }